@@ -0,0 +1,213 @@
+use crate::interface::IBase;
+use crate::object_manager::{read_error, IObjectManager, ObjectManager};
+use crate::{Data, MAX_FRAME_LEN};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use std::rc::Rc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// 读取帧头 返回(头长度,数据长度) 数据不足返回None
+#[inline]
+fn peek_len(src: &[u8]) -> io::Result<Option<(usize, usize)>> {
+    let mut len = 0u64;
+    let mut shift = 0;
+    for (i, b) in src.iter().enumerate() {
+        if shift >= 64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad length"));
+        }
+        len |= ((b & 0x7F) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(Some((i + 1, len as usize)));
+        }
+        shift += 7;
+    }
+    Ok(None)
+}
+
+/// 写入帧头 bit7长度
+#[inline]
+fn put_len<B: BufMut>(dst: &mut B, len: usize) {
+    let mut v = len as u64;
+    while v >= 1 << 7 {
+        dst.put_u8((v & 0x7f | 0x80) as u8);
+        v >>= 7;
+    }
+    dst.put_u8(v as u8);
+}
+
+/// 帧长度超过上限
+#[inline]
+fn frame_too_long(len: usize, max_len: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("frame length {} exceeds max {}", len, max_len))
+}
+
+/// 帧编解码 格式为 bit7长度+数据 与 ObjectManager::write_into 一致
+/// 帧长度上限默认 MAX_FRAME_LEN 超过时返回 InvalidData
+#[derive(Debug, Clone, Copy)]
+pub struct PkgCodec {
+    max_frame_length: usize,
+}
+
+impl PkgCodec {
+    #[inline]
+    pub fn new() -> PkgCodec {
+        PkgCodec { max_frame_length: MAX_FRAME_LEN }
+    }
+
+    /// 帧长度上限
+    #[inline]
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    /// 设置帧长度上限
+    #[inline]
+    pub fn set_max_frame_length(&mut self, max_frame_length: usize) {
+        self.max_frame_length = max_frame_length;
+    }
+}
+
+impl Default for PkgCodec {
+    #[inline]
+    fn default() -> Self {
+        PkgCodec::new()
+    }
+}
+
+impl Decoder for PkgCodec {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    #[inline]
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match peek_len(src)? {
+            None => Ok(None),
+            Some((_, len)) if len > self.max_frame_length => Err(frame_too_long(len, self.max_frame_length)),
+            Some((head, len)) => {
+                if src.len() < head + len {
+                    src.reserve(head + len - src.len());
+                    return Ok(None);
+                }
+                src.advance(head);
+                Ok(Some(src.split_to(len)))
+            }
+        }
+    }
+}
+
+impl Encoder<&[u8]> for PkgCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.len() > self.max_frame_length {
+            return Err(frame_too_long(item.len(), self.max_frame_length));
+        }
+        dst.reserve(item.len() + 10);
+        put_len(dst, item.len());
+        dst.put_slice(item);
+        Ok(())
+    }
+}
+
+/// 对象编解码 解码出 RC<IBASE>
+/// 需要 Send 的场景使用 pkg::sync::codec::ObjectCodec
+pub struct ObjectCodec {
+    obj_manager: Rc<ObjectManager>,
+    frame: PkgCodec,
+    data: Data,
+}
+
+impl ObjectCodec {
+    #[inline]
+    pub fn new(obj_manager: Rc<ObjectManager>) -> ObjectCodec {
+        ObjectCodec {
+            obj_manager,
+            frame: PkgCodec::new(),
+            data: Data::new(),
+        }
+    }
+
+    /// 设置帧长度上限
+    #[inline]
+    pub fn set_max_frame_length(&mut self, max_frame_length: usize) {
+        self.frame.set_max_frame_length(max_frame_length);
+    }
+}
+
+impl Decoder for ObjectCodec {
+    type Item = Rc<dyn IBase>;
+    type Error = io::Error;
+
+    #[inline]
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.frame.decode(src)? {
+            None => Ok(None),
+            Some(frame) => {
+                let mut data = Data::from_bytes(frame.freeze());
+                self.obj_manager.read_core(&mut data).map(Some).map_err(read_error)
+            }
+        }
+    }
+}
+
+impl<T: IBase + ?Sized> Encoder<Rc<T>> for ObjectCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: Rc<T>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.data.clear();
+        self.obj_manager.write_core(&mut self.data, &item);
+        self.frame.encode(Buf::bytes(&self.data), dst)
+    }
+}
+
+/// 从 AsyncRead 读取一帧 长度上限 MAX_FRAME_LEN
+#[inline]
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Vec<u8>> {
+    read_frame_max(reader, MAX_FRAME_LEN).await
+}
+
+/// 从 AsyncRead 读取一帧 长度超过 max_len 返回 InvalidData
+#[inline]
+pub async fn read_frame_max<R: AsyncRead + Unpin>(reader: &mut R, max_len: usize) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(10);
+    let len = loop {
+        head.push(reader.read_u8().await?);
+        if let Some((_, len)) = peek_len(&head)? {
+            break len;
+        }
+    };
+    if len > max_len {
+        return Err(frame_too_long(len, max_len));
+    }
+
+    let mut buff = vec![0u8; len];
+    reader.read_exact(&mut buff).await?;
+    Ok(buff)
+}
+
+/// 写入一帧到 AsyncWrite
+#[inline]
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &[u8]) -> io::Result<()> {
+    let mut buff = BytesMut::with_capacity(frame.len() + 10);
+    PkgCodec::new().encode(frame, &mut buff)?;
+    writer.write_all(&buff).await
+}
+
+/// 从 AsyncRead 读取一个对象
+#[inline]
+pub async fn read_object<R: AsyncRead + Unpin>(reader: &mut R, obj_manager: &ObjectManager) -> io::Result<Rc<dyn IBase>> {
+    let buff = read_frame(reader).await?;
+    let mut data = Data::from_bytes(buff.into());
+    obj_manager.read_core(&mut data).map_err(read_error)
+}
+
+/// 写入一个对象到 AsyncWrite
+#[inline]
+pub async fn write_object<W: AsyncWrite + Unpin, T: IBase + ?Sized>(writer: &mut W, obj_manager: &ObjectManager, arg: &Rc<T>) -> io::Result<()> {
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, arg);
+    write_frame(writer, Buf::bytes(&data)).await
+}
@@ -1,19 +1,99 @@
 use crate::IBase;
 use bytes::buf::UninitSlice;
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes};
 use paste::paste;
 use ahash::AHashMap;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::rc::Rc;
 
+/// 帧头最大长度 bit7 u64 最多10字节
+const FRAME_HEAD: usize = 10;
+
+/// 默认的最大帧长度 与 tokio LengthDelimitedCodec 相同
+pub const MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
 #[derive(Debug)]
-pub struct Data {
+pub struct Data<M = RcTables> {
     buf: Vec<u8>,
+    shared: Option<Bytes>,
     offset: usize,
     pub(crate) w_ptr_dict: AHashMap<usize, u32>,
+    pub(crate) w_count: u32,
+    pub(crate) r_count: u32,
+    pub(crate) read_mode: ReadMode,
+    pub(crate) tables: M,
+}
+
+/// RC 对象模型的引用表 线程安全模型使用 sync::SyncTables
+#[derive(Debug, Default)]
+pub struct RcTables {
+    pub(crate) w_keep: Option<VecDeque<(usize, Box<dyn Any>)>>,
     pub(crate) r_ptr_dict: AHashMap<u32, Rc<dyn IBase>>,
+    pub(crate) ctx: Option<Rc<dyn Any>>,
+}
+
+/// 读取到已有值时的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadMode {
+    /// 新建对象和集合 不保留原有内容
+    Replace,
+    /// 默认 Vec 追加 Map 插入 Option 和 Rc 读取到原有对象 保持对象不变
+    Merge,
+}
+
+impl Default for ReadMode {
+    #[inline]
+    fn default() -> Self {
+        ReadMode::Merge
+    }
 }
 
-unsafe impl BufMut for Data {
+/// 对象模型的引用表 会话和 DecodedGraph 通过它访问两种模型的引用表
+pub(crate) trait Tables: Default {
+    /// 对象指针类型
+    type Ptr;
+    /// 会话中保留的已写入对象
+    type Keep;
+
+    fn r_ptr_dict(&mut self) -> &mut AHashMap<u32, Self::Ptr>;
+    fn w_keep(&mut self) -> &mut Option<VecDeque<(usize, Self::Keep)>>;
+}
+
+impl Tables for RcTables {
+    type Ptr = Rc<dyn IBase>;
+    type Keep = Box<dyn Any>;
+
+    #[inline]
+    fn r_ptr_dict(&mut self) -> &mut AHashMap<u32, Self::Ptr> {
+        &mut self.r_ptr_dict
+    }
+
+    #[inline]
+    fn w_keep(&mut self) -> &mut Option<VecDeque<(usize, Self::Keep)>> {
+        &mut self.w_keep
+    }
+}
+
+/// 对象模型使用的 Data RC 模型为 Data 线程安全模型为 sync::Data
+pub(crate) trait ModelData {
+    type Tables: Tables;
+
+    fn data_mut(&mut self) -> &mut Data<Self::Tables>;
+}
+
+impl ModelData for Data {
+    type Tables = RcTables;
+
+    #[inline]
+    fn data_mut(&mut self) -> &mut Data {
+        self
+    }
+}
+
+unsafe impl<M> BufMut for Data<M> {
     #[inline]
     fn remaining_mut(&self) -> usize {
         self.buf.remaining_mut()
@@ -21,20 +101,20 @@ unsafe impl BufMut for Data {
 
     #[inline]
     unsafe fn advance_mut(&mut self, cnt: usize) {
-        self.buf.advance_mut(cnt);
+        self.buf_mut().advance_mut(cnt);
     }
 
     #[inline]
     fn bytes_mut(&mut self) -> &mut UninitSlice {
-        self.buf.bytes_mut()
+        self.buf_mut().bytes_mut()
     }
 }
 
-impl Buf for Data {
+impl<M> Buf for Data<M> {
     #[inline]
     fn remaining(&self) -> usize {
-        if self.buf.len() > self.offset {
-            self.buf.len() - self.offset
+        if self.len() > self.offset {
+            self.len() - self.offset
         } else {
             0
         }
@@ -45,7 +125,7 @@ impl Buf for Data {
         let len = self.remaining();
 
         if len > 0 {
-            &self.buf[self.offset..]
+            &self.as_slice()[self.offset..]
         } else {
             &[]
         }
@@ -57,15 +137,48 @@ impl Buf for Data {
     }
 }
 
+impl<M> Read for Data<M> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.remaining().min(buf.len());
+        buf[..len].copy_from_slice(&self.as_slice()[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl<M> Write for Data<M> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Data::write(self, buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<M> Seek for Data<M> {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let offset = match pos {
+            SeekFrom::Start(p) => p as i64,
+            SeekFrom::End(p) => self.len() as i64 + p,
+            SeekFrom::Current(p) => self.offset as i64 + p,
+        };
+        if offset < 0 || !self.set_position(offset as usize) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek out of range"));
+        }
+        Ok(self.offset as u64)
+    }
+}
+
 impl Default for Data {
     #[inline]
     fn default() -> Self {
-        Data {
-            buf: Vec::new(),
-            offset: 0,
-            w_ptr_dict: AHashMap::new(),
-            r_ptr_dict: AHashMap::new(),
-        }
+        Data::with_buf(Vec::new(), None)
     }
 }
 
@@ -77,12 +190,89 @@ impl Data {
 
     #[inline]
     pub fn with_capacity(cap: usize) -> Data {
+        Data::with_buf(Vec::with_capacity(cap), None)
+    }
+
+    /// 从Bytes 创建 读取时不复制 写入时转换为独占buff
+    #[inline]
+    pub fn from_bytes(bytes: Bytes) -> Data {
+        Data::with_buf(Vec::new(), Some(bytes))
+    }
+
+    /// 从reader 读取一帧 bit7长度+数据 长度超过 max_len 返回 InvalidData
+    #[inline]
+    pub fn read_frame_io<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Data> {
+        Data::read_frame(reader, max_len)
+    }
+}
+
+impl<M: Default> Data<M> {
+    /// 引用表为空
+    #[inline]
+    pub(crate) fn with_buf(buf: Vec<u8>, shared: Option<Bytes>) -> Data<M> {
         Data {
-            buf: Vec::with_capacity(cap),
+            buf,
+            shared,
             offset: 0,
             w_ptr_dict: AHashMap::new(),
-            r_ptr_dict: AHashMap::new(),
+            w_count: 0,
+            r_count: 0,
+            read_mode: ReadMode::Merge,
+            tables: M::default(),
+        }
+    }
+
+    /// 创建预留帧头的 Data 写入数据后通过 frame 取得 bit7长度+数据
+    #[inline]
+    pub(crate) fn with_frame_head() -> Data<M> {
+        Data::with_buf(vec![0; FRAME_HEAD], None)
+    }
+
+    /// 从reader 读取一帧 bit7长度+数据 长度超过 max_len 返回 InvalidData
+    #[inline]
+    pub(crate) fn read_frame<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Data<M>> {
+        let mut len = 0u64;
+        let mut shift = 0;
+        loop {
+            if shift >= 64 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "bad length"));
+            }
+            let mut b = [0u8; 1];
+            reader.read_exact(&mut b)?;
+            len |= ((b[0] & 0x7F) as u64) << shift;
+            if b[0] & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        if len > max_len as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("frame length {} exceeds {}", len, max_len)));
         }
+
+        let mut data = Data::with_buf(Vec::with_capacity(len as usize), None);
+        data.read_from_io(reader, len as usize)?;
+        Ok(data)
+    }
+}
+
+impl<M> Data<M> {
+    /// 当前数据
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        match self.shared {
+            Some(ref p) => p,
+            None => &self.buf,
+        }
+    }
+
+    /// 写入前转换为独占buff
+    #[inline]
+    fn buf_mut(&mut self) -> &mut Vec<u8> {
+        if let Some(shared) = self.shared.take() {
+            self.buf.clear();
+            self.buf.extend_from_slice(&shared);
+        }
+        &mut self.buf
     }
 
     /// 获取OFFSET
@@ -94,22 +284,44 @@ impl Data {
     /// 设置OFFSET
     #[inline]
     pub fn set_position(&mut self, offset: usize) -> bool {
-        if offset > self.buf.len() {
+        if offset > self.len() {
             return false;
         }
         self.offset = offset;
         return true;
     }
 
+    /// 读取方式
+    #[inline]
+    pub fn read_mode(&self) -> ReadMode {
+        self.read_mode
+    }
+
+    /// 设置读取方式 对之后的原地读取生效 read_from_atomic 总是读取到新创建的值
+    #[inline]
+    pub fn set_read_mode(&mut self, mode: ReadMode) {
+        self.read_mode = mode;
+    }
+
+    /// 集合读取成功后写入读取的元素 Replace 时先清空原有内容 读取失败时不调用 集合不变
+    #[inline]
+    pub(crate) fn merge_items<C: Default + Extend<I>, I>(&self, v: &mut C, items: Vec<I>) {
+        if self.read_mode == ReadMode::Replace {
+            *v = C::default();
+        }
+        v.extend(items);
+    }
+
     /// 长度
     #[inline]
     pub fn len(&self) -> usize {
-        self.buf.len()
+        self.as_slice().len()
     }
 
     /// 重置
     #[inline]
     pub fn reset(&mut self) {
+        self.shared = None;
         self.buf.resize(0, 0);
         self.offset = 0;
     }
@@ -117,12 +329,13 @@ impl Data {
     /// 重置长度
     #[inline]
     pub fn resize(&mut self,new_len:usize,value:u8) {
-        self.buf.resize(new_len, value);
+        self.buf_mut().resize(new_len, value);
     }
 
     /// 清理
     #[inline]
     pub fn clear(&mut self) {
+        self.shared = None;
         self.buf.clear();
         self.offset = 0;
     }
@@ -136,56 +349,113 @@ impl Data {
 
     #[inline]
     pub fn write_ptr(&mut self, buff: *const u8, len: usize) {
-        if self.buf.len() + len > self.buf.capacity() {
-            self.buf.reserve(len);
+        let buf = self.buf_mut();
+        if buf.len() + len > buf.capacity() {
+            buf.reserve(len);
         }
         unsafe {
-            let end = self.buf.as_mut_ptr().add(self.buf.len());
+            let end = buf.as_mut_ptr().add(buf.len());
             end.copy_from(buff, len);
-            self.buf.set_len(self.buf.len() + len);
+            buf.set_len(buf.len() + len);
         }
     }
 
     /// 读取buff
     #[inline]
     pub fn read(&mut self, len: usize) -> Option<Vec<u8>> {
-        if self.buf.len() >= self.offset + len {
-            let data = self.buf[self.offset..self.offset + len].to_vec();
+        self.read_ref(len).map(|p| p.to_vec())
+    }
+
+    /// 读取buff 不复制
+    #[inline]
+    pub fn read_ref(&mut self, len: usize) -> Option<&[u8]> {
+        if len <= self.remaining() {
+            let offset = self.offset;
+            self.offset += len;
+            Some(&self.as_slice()[offset..offset + len])
+        } else {
+            None
+        }
+    }
+
+    /// 读取Bytes 共享buff时不复制
+    #[inline]
+    pub fn read_bytes(&mut self, len: usize) -> Option<Bytes> {
+        if len <= self.remaining() {
+            let offset = self.offset;
             self.offset += len;
-            Some(data)
+            match self.shared {
+                Some(ref p) => Some(p.slice(offset..offset + len)),
+                None => Some(Bytes::copy_from_slice(&self.buf[offset..offset + len])),
+            }
         } else {
             None
         }
     }
+
+    /// 在预留位置填入帧头 返回 bit7长度+数据 数据不复制
+    #[inline]
+    pub(crate) fn frame(&mut self) -> &[u8] {
+        let buf = self.buf_mut();
+        let mut v = (buf.len() - FRAME_HEAD) as u64;
+        let mut head = [0u8; FRAME_HEAD];
+        let mut n = 0;
+        while v >= 1 << 7 {
+            head[n] = (v & 0x7f | 0x80) as u8;
+            v >>= 7;
+            n += 1;
+        }
+        head[n] = v as u8;
+        n += 1;
+        buf[FRAME_HEAD - n..FRAME_HEAD].copy_from_slice(&head[..n]);
+        &buf[FRAME_HEAD - n..]
+    }
+
+    /// 从reader 读取len长度追加到buff
+    #[inline]
+    pub fn read_from_io<R: Read>(&mut self, reader: &mut R, len: usize) -> io::Result<()> {
+        let buf = self.buf_mut();
+        let start = buf.len();
+        buf.resize(start + len, 0);
+        if let Err(err) = reader.read_exact(&mut buf[start..]) {
+            buf.truncate(start);
+            return Err(err);
+        }
+        Ok(())
+    }
+
     /// 写入变成U16
     #[inline]
     pub fn bit7_write_u16(&mut self, value: u16) {
+        let buf = self.buf_mut();
         let mut v = value;
         while v >= 1 << 7 {
-            self.buf.push((v & 0x7f | 0x80) as u8);
+            buf.push((v & 0x7f | 0x80) as u8);
             v = v >> 7;
         }
-        self.buf.push(v as u8);
+        buf.push(v as u8);
     }
     /// 写入变成U32
     #[inline]
     pub fn bit7_write_u32(&mut self, value: u32) {
+        let buf = self.buf_mut();
         let mut v = value;
         while v >= 1 << 7 {
-            self.buf.push((v & 0x7f | 0x80) as u8);
+            buf.push((v & 0x7f | 0x80) as u8);
             v = v >> 7;
         }
-        self.buf.push(v as u8);
+        buf.push(v as u8);
     }
     /// 写入变成U64
     #[inline]
     pub fn bit7_write_u64(&mut self, value: u64) {
+        let buf = self.buf_mut();
         let mut v = value;
         while v >= 1 << 7 {
-            self.buf.push((v & 0x7f | 0x80) as u8);
+            buf.push((v & 0x7f | 0x80) as u8);
             v = v >> 7;
         }
-        self.buf.push(v as u8);
+        buf.push(v as u8);
     }
     /// 写入变长i64
     #[inline]
@@ -205,25 +475,9 @@ impl Data {
     /// 读取变长 u16
     #[inline]
     pub fn read_bit7_u16(&mut self) -> Option<(usize, u16)> {
-        let mut v = 0;
-        let mut offset = self.offset;
-        let mut shift = 0;
-        while shift < 2 * 8 {
-            if offset >= self.buf.len() {
-                return None;
-            }
-
-            let b = self.buf[offset];
-            offset += 1;
-            v |= ((b & 0x7F) as u16) << shift;
-            if b & 0x80 == 0 {
-                let len = offset - self.offset;
-                self.offset = offset;
-                return Some((len, v));
-            }
-            shift += 7;
-        }
-        None
+        let (len, v) = decode_bit7(self.as_slice(), self.offset, 16)?;
+        self.offset += len;
+        Some((len, v as u16))
     }
     /// 读取变长i16
     #[inline]
@@ -238,25 +492,9 @@ impl Data {
     /// 读取变长u32
     #[inline]
     pub fn read_bit7_u32(&mut self) -> Option<(usize, u32)> {
-        let mut v = 0;
-        let mut offset = self.offset;
-        let mut shift = 0;
-        while shift < 4 * 8 {
-            if offset >= self.buf.len() {
-                return None;
-            }
-
-            let b = self.buf[offset];
-            offset += 1;
-            v |= ((b & 0x7F) as u32) << shift;
-            if b & 0x80 == 0 {
-                let len = offset - self.offset;
-                self.offset = offset;
-                return Some((len, v));
-            }
-            shift += 7;
-        }
-        None
+        let (len, v) = decode_bit7(self.as_slice(), self.offset, 32)?;
+        self.offset += len;
+        Some((len, v as u32))
     }
     /// 读取变长i32
     #[inline]
@@ -271,25 +509,9 @@ impl Data {
     /// 读取变长u64
     #[inline]
     pub fn read_bit7_u64(&mut self) -> Option<(usize, u64)> {
-        let mut v = 0;
-        let mut offset = self.offset;
-        let mut shift = 0;
-        while shift < 8 * 8 {
-            if offset >= self.buf.len() {
-                return None;
-            }
-
-            let b = self.buf[offset];
-            offset += 1;
-            v |= ((b & 0x7F) as u64) << shift;
-            if b & 0x80 == 0 {
-                let len = offset - self.offset;
-                self.offset = offset;
-                return Some((len, v));
-            }
-            shift += 7;
-        }
-        None
+        let (len, v) = decode_bit7(self.as_slice(), self.offset, 64)?;
+        self.offset += len;
+        Some((len, v))
     }
     /// 读取变长i64
     #[inline]
@@ -354,11 +576,43 @@ impl Data {
         }
     }
 
+    /// 读取二进制 变长长度 不复制
+    #[inline]
+    pub fn get_buff_bit7_ref(&mut self) -> Option<&[u8]> {
+        let res = self.get_bit7::<u64>();
+        if let Some((_, len)) = res {
+            self.read_ref(len as usize)
+        } else {
+            None
+        }
+    }
+
+    /// 读取二进制 变长长度 返回Bytes 共享buff时不复制
+    #[inline]
+    pub fn get_bytes_bit7(&mut self) -> Option<Bytes> {
+        let res = self.get_bit7::<u64>();
+        if let Some((_, len)) = res {
+            self.read_bytes(len as usize)
+        } else {
+            None
+        }
+    }
+
     /// 读取字符串 变长
     #[inline]
     pub fn get_str_bit7(&mut self) -> Option<String> {
-        if let Some(buff) = self.get_buff_bit7() {
-            Some(String::from_utf8_lossy(&buff).to_string())
+        if let Some(buff) = self.get_buff_bit7_ref() {
+            Some(String::from_utf8_lossy(buff).into_owned())
+        } else {
+            None
+        }
+    }
+
+    /// 读取字符串 变长 不复制 非UTF8返回None
+    #[inline]
+    pub fn get_str_bit7_ref(&mut self) -> Option<&str> {
+        if let Some(buff) = self.get_buff_bit7_ref() {
+            std::str::from_utf8(buff).ok()
         } else {
             None
         }
@@ -402,6 +656,28 @@ impl Data {
         }
     }
 }
+/// 从 offset 读取最多 bits 位的变长整数 返回 (长度,值)
+#[inline]
+pub(crate) fn decode_bit7(buf: &[u8], offset: usize, bits: u32) -> Option<(usize, u64)> {
+    let mut v = 0;
+    let mut i = offset;
+    let mut shift = 0;
+    while shift < bits {
+        if i >= buf.len() {
+            return None;
+        }
+
+        let b = buf[i];
+        i += 1;
+        v |= ((b & 0x7F) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Some((i - offset, v));
+        }
+        shift += 7;
+    }
+    None
+}
+
 #[inline]
 fn zig_zag_encode_u16(v: i16) -> u16 {
     ((v << 1) ^ (v >> 15)) as u16
@@ -415,19 +691,19 @@ fn zig_zag_encode_u64(v: i64) -> u64 {
     ((v << 1) ^ (v >> 63)) as u64
 }
 #[inline]
-fn zig_zag_decode_i16(v: u16) -> i16 {
+pub(crate) fn zig_zag_decode_i16(v: u16) -> i16 {
     ((v >> 1) as i16) ^ (-((v & 1) as i16))
 }
 #[inline]
-fn zig_zag_decode_i32(v: u32) -> i32 {
+pub(crate) fn zig_zag_decode_i32(v: u32) -> i32 {
     ((v >> 1) as i32) ^ (-((v & 1) as i32))
 }
 #[inline]
-fn zig_zag_decode_i64(v: u64) -> i64 {
+pub(crate) fn zig_zag_decode_i64(v: u64) -> i64 {
     ((v >> 1) as i64) ^ (-((v & 1) as i64))
 }
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn write_to_le<T: Writer>(&mut self, p: T) {
         p.write_to_le(self)
@@ -439,8 +715,8 @@ impl Data {
 }
 
 pub trait Writer: Copy {
-    fn write_to_le(&self, data: &mut Data);
-    fn write_to(&self, data: &mut Data);
+    fn write_to_le<M>(&self, data: &mut Data<M>);
+    fn write_to<M>(&self, data: &mut Data<M>);
 }
 
 macro_rules! make_writer {
@@ -448,11 +724,11 @@ macro_rules! make_writer {
         impl Writer for $type {
             paste! {
              #[inline]
-            fn write_to_le(&self, data:&mut Data) {
+            fn write_to_le<M>(&self, data: &mut Data<M>) {
                data.[<put_ $type _le>](*self)
             }
              #[inline]
-            fn write_to(&self, data: &mut Data) {
+            fn write_to<M>(&self, data: &mut Data<M>) {
                 data.[<put_ $type>](*self);
             }
             }
@@ -461,7 +737,7 @@ macro_rules! make_writer {
 }
 impl Writer for bool {
     #[inline]
-    fn write_to_le(&self, data: &mut Data) {
+    fn write_to_le<M>(&self, data: &mut Data<M>) {
         if *self {
             data.put_u8(1);
         } else {
@@ -469,7 +745,7 @@ impl Writer for bool {
         }
     }
     #[inline]
-    fn write_to(&self, data: &mut Data) {
+    fn write_to<M>(&self, data: &mut Data<M>) {
         if *self {
             data.put_u8(1);
         } else {
@@ -480,22 +756,22 @@ impl Writer for bool {
 
 impl Writer for u8 {
     #[inline]
-    fn write_to_le(&self, data: &mut Data) {
+    fn write_to_le<M>(&self, data: &mut Data<M>) {
         data.put_u8(*self);
     }
     #[inline]
-    fn write_to(&self, data: &mut Data) {
+    fn write_to<M>(&self, data: &mut Data<M>) {
         data.put_u8(*self);
     }
 }
 
 impl Writer for i8 {
     #[inline]
-    fn write_to_le(&self, data: &mut Data) {
+    fn write_to_le<M>(&self, data: &mut Data<M>) {
         data.put_i8(*self);
     }
     #[inline]
-    fn write_to(&self, data: &mut Data) {
+    fn write_to<M>(&self, data: &mut Data<M>) {
         data.put_i8(*self);
     }
 }
@@ -512,7 +788,7 @@ make_writer!(i128);
 make_writer!(f32);
 make_writer!(f64);
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn get<T: Reader>(&mut self) -> T {
         T::get(self)
@@ -524,21 +800,21 @@ impl Data {
 }
 
 pub trait Reader {
-    fn get(data: &mut Data) -> Self;
-    fn get_le(data: &mut Data) -> Self;
+    fn get<M>(data: &mut Data<M>) -> Self;
+    fn get_le<M>(data: &mut Data<M>) -> Self;
 }
 
 macro_rules! make_reader {
     ($type:ty) => {
         impl Reader for $type {
             #[inline]
-            fn get(data: &mut Data) -> Self {
+            fn get<M>(data: &mut Data<M>) -> Self {
                 paste! {
                  data.[<get_ $type>]()
                 }
             }
             #[inline]
-            fn get_le(data: &mut Data) -> Self {
+            fn get_le<M>(data: &mut Data<M>) -> Self {
                 paste! {
                  data.[<get_ $type _le>]()
                 }
@@ -549,7 +825,7 @@ macro_rules! make_reader {
 
 impl Reader for bool {
     #[inline]
-    fn get(data: &mut Data) -> Self {
+    fn get<M>(data: &mut Data<M>) -> Self {
         if data.get_u8() == 1 {
             true
         } else {
@@ -557,7 +833,7 @@ impl Reader for bool {
         }
     }
     #[inline]
-    fn get_le(data: &mut Data) -> Self {
+    fn get_le<M>(data: &mut Data<M>) -> Self {
         if data.get_u8() == 1 {
             true
         } else {
@@ -567,21 +843,21 @@ impl Reader for bool {
 }
 impl Reader for u8 {
     #[inline]
-    fn get(data: &mut Data) -> Self {
+    fn get<M>(data: &mut Data<M>) -> Self {
         data.get_u8()
     }
     #[inline]
-    fn get_le(data: &mut Data) -> Self {
+    fn get_le<M>(data: &mut Data<M>) -> Self {
         data.get_u8()
     }
 }
 impl Reader for i8 {
     #[inline]
-    fn get(data: &mut Data) -> Self {
+    fn get<M>(data: &mut Data<M>) -> Self {
         data.get_i8()
     }
     #[inline]
-    fn get_le(data: &mut Data) -> Self {
+    fn get_le<M>(data: &mut Data<M>) -> Self {
         data.get_i8()
     }
 }
@@ -597,7 +873,7 @@ make_reader!(u128);
 make_reader!(f32);
 make_reader!(f64);
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn write_bit7<T: WriteBit7>(&mut self, p: T) {
         p.write_bit7(self);
@@ -605,14 +881,14 @@ impl Data {
 }
 
 pub trait WriteBit7: Copy {
-    fn write_bit7(&self, data: &mut Data);
+    fn write_bit7<M>(&self, data: &mut Data<M>);
 }
 
 macro_rules! make_write_bit7 {
     ($type:ty) => {
         impl WriteBit7 for $type {
             #[inline]
-            fn write_bit7(&self, data: &mut Data) {
+            fn write_bit7<M>(&self, data: &mut Data<M>) {
                 paste! {
                   data.[<bit7_write_ $type>](*self)
                 }
@@ -628,7 +904,7 @@ make_write_bit7!(u16);
 make_write_bit7!(u32);
 make_write_bit7!(u64);
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn get_bit7<T: ReadBit7>(&mut self) -> Option<(usize, T::RetType)> {
         T::get_bit7(self)
@@ -637,7 +913,7 @@ impl Data {
 
 pub trait ReadBit7 {
     type RetType;
-    fn get_bit7(data: &mut Data) -> Option<(usize, Self::RetType)>;
+    fn get_bit7<M>(data: &mut Data<M>) -> Option<(usize, Self::RetType)>;
 }
 
 macro_rules! make_read_bit7 {
@@ -645,7 +921,7 @@ macro_rules! make_read_bit7 {
         impl ReadBit7 for $type {
             type RetType = $type;
             #[inline]
-            fn get_bit7(data: &mut Data) -> Option<(usize, Self::RetType)> {
+            fn get_bit7<M>(data: &mut Data<M>) -> Option<(usize, Self::RetType)> {
                 paste! {
                 data.[<read_bit7_ $type>]()
                 }
@@ -661,7 +937,7 @@ make_read_bit7!(u16);
 make_read_bit7!(u32);
 make_read_bit7!(u64);
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn write_core<T: WriteCore>(&mut self, p: &T) {
         p.write_core(self)
@@ -669,26 +945,26 @@ impl Data {
 }
 
 pub trait WriteCore {
-    fn write_core(&self, data: &mut Data);
+    fn write_core<M>(&self, data: &mut Data<M>);
 }
 
 impl WriteCore for &str {
     #[inline]
-    fn write_core(&self, data: &mut Data) {
+    fn write_core<M>(&self, data: &mut Data<M>) {
         data.write_str_bit7(self)
     }
 }
 
 impl WriteCore for String {
     #[inline]
-    fn write_core(&self, data: &mut Data) {
+    fn write_core<M>(&self, data: &mut Data<M>) {
         data.write_str_bit7(self)
     }
 }
 
 impl WriteCore for &[u8] {
     #[inline]
-    fn write_core(&self, data: &mut Data) {
+    fn write_core<M>(&self, data: &mut Data<M>) {
         data.write_buff_bit7(self)
     }
 }
@@ -697,7 +973,7 @@ macro_rules! make_write_core_bit7 {
     ($type:ty) => {
         impl WriteCore for Vec<$type> {
             #[inline]
-            fn write_core(&self, data: &mut Data) {
+            fn write_core<M>(&self, data: &mut Data<M>) {
                 data.write_bit7(self.len() as u64);
                 for i in self {
                     data.write_bit7(*i);
@@ -718,7 +994,7 @@ macro_rules! make_write_core {
     ($type:ty) => {
         impl WriteCore for Vec<$type> {
             #[inline]
-            fn write_core(&self, data: &mut Data) {
+            fn write_core<M>(&self, data: &mut Data<M>) {
                 data.write_bit7(self.len() as u64);
                 for i in self {
                     data.write_to_le(*i);
@@ -735,7 +1011,7 @@ make_write_core!(u128);
 
 impl<T: WriteCore> WriteCore for Vec<T> {
     #[inline]
-    fn write_core(&self, data: &mut Data) {
+    fn write_core<M>(&self, data: &mut Data<M>) {
         data.write_bit7(self.len() as u64);
         for i in self.iter() {
             data.write_core(i);
@@ -743,7 +1019,7 @@ impl<T: WriteCore> WriteCore for Vec<T> {
     }
 }
 
-impl Data {
+impl<M> Data<M> {
     #[inline]
     pub fn read_core<T: ReadCore>(&mut self) -> T {
         T::read_core(self)
@@ -751,14 +1027,14 @@ impl Data {
 }
 
 pub trait ReadCore {
-    fn read_core(data: &mut Data) -> Self;
+    fn read_core<M>(data: &mut Data<M>) -> Self;
 }
 
 macro_rules! make_read_core_bit7 {
     ($type:ty) => {
         impl ReadCore for Vec<$type> {
             #[inline]
-            fn read_core(data: &mut Data) -> Self {
+            fn read_core<M>(data: &mut Data<M>) -> Self {
                 let (_, len) = data.get_bit7::<u64>().unwrap();
                 let mut ret = Vec::with_capacity(len as usize);
                 for _ in 0..len {
@@ -782,7 +1058,7 @@ macro_rules! make_read_core_le {
     ($type:ty) => {
         impl ReadCore for Vec<$type> {
             #[inline]
-            fn read_core(data: &mut Data) -> Self {
+            fn read_core<M>(data: &mut Data<M>) -> Self {
                 let (_, len) = data.get_bit7::<u64>().unwrap();
                 let mut ret = Vec::with_capacity(len as usize);
                 for _ in 0..len {
@@ -801,14 +1077,14 @@ make_read_core_le!(f64);
 
 impl ReadCore for String {
     #[inline]
-    fn read_core(data: &mut Data) -> Self {
+    fn read_core<M>(data: &mut Data<M>) -> Self {
         data.get_str_bit7().unwrap()
     }
 }
 
 impl<T: ReadCore> ReadCore for Vec<T> {
     #[inline]
-    fn read_core(data: &mut Data) -> Self {
+    fn read_core<M>(data: &mut Data<M>) -> Self {
         let (_, len) = data.get_bit7::<u64>().unwrap();
         let mut ret: Vec<T> = Vec::with_capacity(len as usize);
         for _ in 0..len {
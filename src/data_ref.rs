@@ -0,0 +1,149 @@
+use crate::data::{decode_bit7, zig_zag_decode_i16, zig_zag_decode_i32, zig_zag_decode_i64};
+use bytes::Buf;
+
+/// 借用 &[u8] 的只读 Data 读取时不复制 返回的切片和字符串借用原始数据
+/// 对象读取需要引用表 仍然使用 Data::from_bytes
+#[derive(Debug, Clone, Copy)]
+pub struct DataRef<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> From<&'a [u8]> for DataRef<'a> {
+    #[inline]
+    fn from(buf: &'a [u8]) -> Self {
+        DataRef::new(buf)
+    }
+}
+
+impl<'a> Buf for DataRef<'a> {
+    #[inline]
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.offset
+    }
+
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.buf[self.offset..]
+    }
+
+    #[inline]
+    fn advance(&mut self, cnt: usize) {
+        assert!(cnt <= self.remaining(), "advance out of range");
+        self.offset += cnt;
+    }
+}
+
+impl<'a> DataRef<'a> {
+    #[inline]
+    pub fn new(buf: &'a [u8]) -> DataRef<'a> {
+        DataRef { buf, offset: 0 }
+    }
+
+    /// 全部数据
+    #[inline]
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    /// 长度
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// 获取OFFSET
+    #[inline]
+    pub fn get_position(&self) -> usize {
+        self.offset
+    }
+
+    /// 设置OFFSET
+    #[inline]
+    pub fn set_position(&mut self, offset: usize) -> bool {
+        if offset > self.buf.len() {
+            return false;
+        }
+        self.offset = offset;
+        true
+    }
+
+    /// 读取buff 不复制
+    #[inline]
+    pub fn read_ref(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.remaining() {
+            return None;
+        }
+        let offset = self.offset;
+        self.offset += len;
+        Some(&self.buf[offset..offset + len])
+    }
+
+    /// 读取变长 u16
+    #[inline]
+    pub fn read_bit7_u16(&mut self) -> Option<(usize, u16)> {
+        let (len, v) = decode_bit7(self.buf, self.offset, 16)?;
+        self.offset += len;
+        Some((len, v as u16))
+    }
+
+    /// 读取变长i16
+    #[inline]
+    pub fn read_bit7_i16(&mut self) -> Option<(usize, i16)> {
+        let (len, v) = self.read_bit7_u16()?;
+        Some((len, zig_zag_decode_i16(v)))
+    }
+
+    /// 读取变长u32
+    #[inline]
+    pub fn read_bit7_u32(&mut self) -> Option<(usize, u32)> {
+        let (len, v) = decode_bit7(self.buf, self.offset, 32)?;
+        self.offset += len;
+        Some((len, v as u32))
+    }
+
+    /// 读取变长i32
+    #[inline]
+    pub fn read_bit7_i32(&mut self) -> Option<(usize, i32)> {
+        let (len, v) = self.read_bit7_u32()?;
+        Some((len, zig_zag_decode_i32(v)))
+    }
+
+    /// 读取变长u64
+    #[inline]
+    pub fn read_bit7_u64(&mut self) -> Option<(usize, u64)> {
+        let (len, v) = decode_bit7(self.buf, self.offset, 64)?;
+        self.offset += len;
+        Some((len, v))
+    }
+
+    /// 读取变长i64
+    #[inline]
+    pub fn read_bit7_i64(&mut self) -> Option<(usize, i64)> {
+        let (len, v) = self.read_bit7_u64()?;
+        Some((len, zig_zag_decode_i64(v)))
+    }
+
+    /// 读取二进制 变长长度 不复制 失败时读取位置不变
+    #[inline]
+    pub fn get_buff_bit7_ref(&mut self) -> Option<&'a [u8]> {
+        let offset = self.offset;
+        let (_, len) = self.read_bit7_u64()?;
+        if len > self.remaining() as u64 {
+            self.offset = offset;
+            return None;
+        }
+        self.read_ref(len as usize)
+    }
+
+    /// 读取字符串 变长 不复制 非UTF8返回None
+    #[inline]
+    pub fn get_str_bit7_ref(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.get_buff_bit7_ref()?).ok()
+    }
+}
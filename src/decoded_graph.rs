@@ -0,0 +1,84 @@
+use crate::data::{ModelData, Tables};
+use crate::interface::IBase;
+use crate::object_manager::{ObjectManager, ReadObject};
+use crate::Data;
+use std::mem::take;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// 读取结果和读取时创建的所有对象
+/// 持有所有对象 只被 Weak 引用的对象在 DecodedGraph 释放前保持有效
+/// P 为对象指针类型 线程安全模型为 Arc<dyn sync::IBase>
+pub struct DecodedGraph<T, P = Rc<dyn IBase>> {
+    value: T,
+    objects: Vec<P>,
+}
+
+impl<T, P> DecodedGraph<T, P> {
+    #[inline]
+    pub(crate) fn new(value: T, objects: Vec<P>) -> DecodedGraph<T, P> {
+        DecodedGraph { value, objects }
+    }
+
+    #[inline]
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// 读取时创建的所有对象 按编号排序
+    #[inline]
+    pub fn objects(&self) -> &[P] {
+        &self.objects
+    }
+
+    /// 拆分为读取结果和所有对象
+    #[inline]
+    pub fn into_parts(self) -> (T, Vec<P>) {
+        (self.value, self.objects)
+    }
+}
+
+impl<T, P> Deref for DecodedGraph<T, P> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// 取出引用表中的对象 按编号排序 与 read_from 相同清空引用表
+#[inline]
+pub(crate) fn take_objects<D: ModelData>(data: &mut D) -> Vec<<D::Tables as Tables>::Ptr> {
+    let data = data.data_mut();
+    let mut objects: Vec<_> = take(data.tables.r_ptr_dict()).into_iter().collect();
+    objects.sort_by_key(|(offs, _)| *offs);
+    data.r_count = 0;
+    objects.into_iter().map(|(_, v)| v).collect()
+}
+
+impl ObjectManager {
+    /// 读取一个预设值 并持有读取时创建的所有对象 失败时恢复读取位置
+    #[inline]
+    pub fn read_graph<T: ReadObject + Default>(&self, data: &mut Data) -> Result<DecodedGraph<T>, u32> {
+        let offset = data.get_position();
+        let mut value = T::default();
+        let ret = value.read_(data, self);
+        let objects = take_objects(data);
+        if let Err(err) = ret {
+            data.set_position(offset);
+            return Err(err);
+        }
+        Ok(DecodedGraph { value, objects })
+    }
+
+    /// 读取一个 RC<IBASE> 并持有读取时创建的所有对象
+    #[inline]
+    pub fn read_core_graph(&self, data: &mut Data) -> Result<DecodedGraph<Rc<dyn IBase>>, u32> {
+        let graph = self.read_graph::<Option<Rc<dyn IBase>>>(data)?;
+        match graph.value {
+            None => Err(line!()),
+            Some(value) => Ok(DecodedGraph { value, objects: graph.objects }),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::decoded_graph::DecodedGraph;
+use crate::interface::{IBase, IBaseAsRc, IObjectBase};
+use crate::object_manager::{IObjectManager, ObjectManager};
+use crate::Data;
+use ahash::AHashMap;
+use bytes::Bytes;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+/// 深度复制接口 把自身字段复制到默认值创建的 target
+/// 实现时在 impl IBase 中使用 deep_clone_dyn! 生成 IBase::deep_clone_dyn
+pub trait IDeepClone: IObjectBase {
+    fn deep_clone_into(&self, target: &Self, map: &mut CloneMap);
+}
+
+/// 值的深度复制
+pub trait DeepClone {
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self;
+}
+
+/// 结构字段的深度复制 复制到 target 不需要可变引用
+pub trait DeepCloneField {
+    fn deep_clone_to(&self, target: &Self, map: &mut CloneMap);
+}
+
+/// 复制表 原对象地址到复制的对象 与 w_ptr_dict 相同按地址识别共享对象
+/// 持有所有复制的对象 复制结果与 DecodedGraph 相同 只被 Weak 引用的对象在结果释放前保持有效
+#[derive(Default)]
+pub struct CloneMap {
+    dict: AHashMap<usize, usize>,
+    objects: Vec<Rc<dyn IBase>>,
+    error: Option<u32>,
+}
+
+impl CloneMap {
+    #[inline]
+    pub fn new() -> CloneMap {
+        CloneMap::default()
+    }
+
+    /// 复制 Rc 中的对象 同一个对象只复制一次
+    #[inline]
+    pub fn clone_obj<T: IDeepClone>(&mut self, src: &T) -> Rc<T> {
+        let addr = src as *const T as *const u8 as usize;
+        if let Some(&index) = self.dict.get(&addr) {
+            if let Ok(v) = self.objects[index].clone().cast::<T>() {
+                return v;
+            }
+        }
+        let v = Rc::new(T::default());
+        self.dict.insert(addr, self.objects.len());
+        self.objects.push(v.clone());
+        src.deep_clone_into(&v, self);
+        v
+    }
+
+    /// 记录复制失败 只保留第一个错误
+    #[inline]
+    pub fn fail(&mut self, err: u32) {
+        self.error.get_or_insert(err);
+    }
+
+    /// 结束复制 返回复制结果和所有复制的对象 复制中有错误时返回错误
+    #[inline]
+    pub fn finish<T>(self, value: T) -> Result<DecodedGraph<T>, u32> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(DecodedGraph::new(value, self.objects)),
+        }
+    }
+
+    /// 用于结构 复制字段
+    #[inline]
+    pub fn clone_field<T: DeepCloneField>(&mut self, src: &T, target: &T) {
+        src.deep_clone_to(target, self)
+    }
+}
+
+impl ObjectManager {
+    /// 深度复制对象图 共享的 Rc 只复制一次 Weak 指向复制后的对象
+    /// 返回复制结果和所有复制的对象 与 read_core_graph 相同
+    /// 根对象没有实现 IDeepClone 时通过 write_core/read_core_graph 复制
+    /// 根对象实现了 IDeepClone 但内部的 Rc<dyn IBase> 没有实现时返回错误
+    #[inline]
+    pub fn deep_clone(&self, obj: &Rc<dyn IBase>) -> Result<DecodedGraph<Rc<dyn IBase>>, u32> {
+        let mut map = CloneMap::new();
+        if let Some(v) = obj.deep_clone_dyn(&mut map) {
+            return map.finish(v);
+        }
+        let mut data = Data::new();
+        self.write_core(&mut data, obj);
+        self.read_core_graph(&mut data)
+    }
+}
+
+impl<T: IDeepClone> DeepClone for T {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        let v = T::default();
+        self.deep_clone_into(&v, map);
+        v
+    }
+}
+
+impl<T: IDeepClone> DeepCloneField for T {
+    #[inline]
+    fn deep_clone_to(&self, target: &Self, map: &mut CloneMap) {
+        self.deep_clone_into(target, map)
+    }
+}
+
+impl<T: DeepClone + Copy> DeepCloneField for Cell<T> {
+    #[inline]
+    fn deep_clone_to(&self, target: &Self, map: &mut CloneMap) {
+        target.set(self.get().deep_clone_(map))
+    }
+}
+
+impl<T: DeepClone> DeepCloneField for RefCell<T> {
+    #[inline]
+    fn deep_clone_to(&self, target: &Self, map: &mut CloneMap) {
+        let v = self.borrow().deep_clone_(map);
+        *target.borrow_mut() = v;
+    }
+}
+
+macro_rules! impl_deep_clone {
+    ($($type:ty),+) => {
+        $(
+            impl DeepClone for $type {
+                #[inline]
+                fn deep_clone_(&self, _: &mut CloneMap) -> Self {
+                    self.clone()
+                }
+            }
+        )+
+    };
+}
+
+impl_deep_clone!(bool, i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, f32, f64, String, Bytes);
+
+impl<T: IDeepClone> DeepClone for Rc<T> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        map.clone_obj(&**self)
+    }
+}
+
+/// 没有实现 IDeepClone 的类型不能复制 记录错误 deep_clone 返回错误
+impl DeepClone for Rc<dyn IBase> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        match (**self).deep_clone_dyn(map) {
+            Some(v) => v,
+            None => {
+                map.fail(line!());
+                self.clone()
+            }
+        }
+    }
+}
+
+impl<T: IDeepClone> DeepClone for Weak<T> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        match self.upgrade() {
+            Some(v) => Rc::downgrade(&map.clone_obj(&*v)),
+            None => Weak::new(),
+        }
+    }
+}
+
+impl<T: DeepClone> DeepClone for Option<T> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        self.as_ref().map(|v| v.deep_clone_(map))
+    }
+}
+
+impl<T: DeepClone> DeepClone for Vec<T> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        self.iter().map(|v| v.deep_clone_(map)).collect()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: DeepClone> DeepClone for HashMap<K, V> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        self.iter().map(|(k, v)| (k.clone(), v.deep_clone_(map))).collect()
+    }
+}
+
+impl<K: Clone + Ord, V: DeepClone> DeepClone for BTreeMap<K, V> {
+    #[inline]
+    fn deep_clone_(&self, map: &mut CloneMap) -> Self {
+        self.iter().map(|(k, v)| (k.clone(), v.deep_clone_(map))).collect()
+    }
+}
+
+/// 在 impl IBase 中生成 deep_clone_dyn 通过 IDeepClone 复制 类型没有实现 IDeepClone 时编译失败
+#[macro_export]
+macro_rules! deep_clone_dyn {
+    () => {
+        #[inline]
+        fn deep_clone_dyn(
+            &self,
+            map: &mut $crate::deep_clone::CloneMap,
+        ) -> Option<std::rc::Rc<dyn $crate::interface::IBase>> {
+            Some(map.clone_obj::<Self>(self))
+        }
+    };
+}
+
+/// 按字段生成 IDeepClone 逐字段调用 CloneMap::clone_field
+/// impl_deep_clone_fields!(Type { a, b }) 字段需要实现 DeepCloneField
+#[macro_export]
+macro_rules! impl_deep_clone_fields {
+    ($($ty:ident { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::deep_clone::IDeepClone for $ty {
+                #[inline]
+                fn deep_clone_into(&self, target: &Self, map: &mut $crate::deep_clone::CloneMap) {
+                    $(map.clone_field(&self.$field, &target.$field);)*
+                }
+            }
+        )+
+    };
+}
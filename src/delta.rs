@@ -0,0 +1,664 @@
+use crate::interface::{GetValue, IBase, IObjectBase};
+use crate::object_manager::{ObjectManager, ReadObject, WriteObject};
+use crate::Data;
+use bytes::{Buf, BufMut, Bytes};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+/// 变化掩码最多支持的字段数
+pub const MAX_DELTA_FIELDS: usize = 64;
+
+/// 第 index 个字段在变化掩码中的位 超过 MAX_DELTA_FIELDS 时 panic
+/// 在 const 中使用时超过上限编译失败
+#[inline]
+pub const fn field_bit(index: usize) -> u64 {
+    assert!(index < MAX_DELTA_FIELDS, "delta mask supports at most 64 fields");
+    1 << index
+}
+
+/// 字段在字段列表中的序号 impl_delta_fields! 和 impl_track_changes! 通过它计算掩码位
+/// 同一结构两种掩码的序号相同 都是字段在列表中的位置
+#[doc(hidden)]
+pub const fn field_index(fields: &[&str], name: &str) -> usize {
+    let mut i = 0;
+    while i < fields.len() {
+        if str_eq(fields[i], name) {
+            return i;
+        }
+        i += 1;
+    }
+    panic!("field not in list");
+}
+
+#[inline]
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// 差异编码接口 结构按字段写入变化掩码和变化的字段 最多64个字段 掩码位通过 field_bit 计算
+/// 可以通过 impl_delta_fields! 按字段列表生成
+pub trait IDelta: IBase {
+    /// 是否与旧值不同
+    fn changed(&self, old: &Self, o: &ObjectManager) -> bool;
+    /// 写入与旧值的差异
+    fn write_delta(&self, old: &Self, data: &mut Data, o: &ObjectManager);
+    /// 读取差异并应用
+    fn apply_delta(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
+}
+
+/// 值的差异编码
+pub trait DeltaValue {
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool;
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager);
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
+}
+
+/// 结构字段的差异编码 应用时不需要可变引用
+pub trait DeltaField {
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool;
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager);
+    fn apply_delta_(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
+}
+
+/// 脏字段掩码 每个字段一位 字段序号超过 MAX_DELTA_FIELDS 时 panic
+/// 字段应为私有 只通过 set/set_cell/replace 等标记脏位的设置方法修改
+#[derive(Debug, Default)]
+pub struct DirtyMask(Cell<u64>);
+
+impl DirtyMask {
+    /// 标记字段改变
+    #[inline]
+    pub fn mark(&self, index: usize) {
+        self.0.set(self.0.get() | field_bit(index));
+    }
+
+    /// 执行 f 并标记字段改变 用于包装自定义的设置方法
+    #[inline]
+    pub fn update<R, F: FnOnce() -> R>(&self, index: usize, f: F) -> R {
+        let ret = f();
+        self.mark(index);
+        ret
+    }
+
+    /// 通过 GetValue::set 设置字段并标记改变
+    #[inline]
+    pub fn set<V: GetValue>(&self, index: usize, field: &V, v: V::ReturnType) {
+        self.update(index, || field.set(v))
+    }
+
+    /// 设置 Cell 字段并标记改变
+    #[inline]
+    pub fn set_cell<T: Copy>(&self, index: usize, field: &Cell<T>, v: T) {
+        self.update(index, || field.set(v))
+    }
+
+    /// 替换 RefCell 字段并标记改变 返回旧值
+    #[inline]
+    pub fn replace<T>(&self, index: usize, field: &RefCell<T>, v: T) -> T {
+        self.update(index, || field.replace(v))
+    }
+
+    #[inline]
+    pub fn is_dirty(&self, index: usize) -> bool {
+        self.0.get() & field_bit(index) != 0
+    }
+
+    #[inline]
+    pub fn get(&self) -> u64 {
+        self.0.get()
+    }
+
+    /// 返回并清空掩码
+    #[inline]
+    pub fn take(&self) -> u64 {
+        self.0.replace(0)
+    }
+}
+
+/// 变化跟踪接口 track_changes 模式的结构通过设置方法记录脏字段 最多64个字段
+/// 可以通过 impl_track_changes! 按字段列表生成
+pub trait ITrackChanges: IBase {
+    fn dirty_mask(&self) -> &DirtyMask;
+    /// 写入掩码中的字段
+    fn write_fields(&self, mask: u64, data: &mut Data, o: &ObjectManager);
+    /// 读取掩码中的字段
+    fn read_fields(&self, mask: u64, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
+
+    /// 返回并清空脏字段掩码
+    #[inline]
+    fn take_dirty(&self) -> u64 {
+        self.dirty_mask().take()
+    }
+}
+
+impl ObjectManager {
+    /// 写入上次写入后改变的字段 并清空脏字段掩码
+    #[inline]
+    pub fn write_dirty<T: ITrackChanges>(&self, data: &mut Data, obj: &T) {
+        data.w_ptr_dict.clear();
+        data.w_count = 0;
+        let mask = obj.take_dirty();
+        data.write_bit7(mask);
+        obj.write_fields(mask, data, self);
+    }
+
+    /// 读取 write_dirty 写入的字段 失败时恢复读取位置 已读取的字段不恢复
+    #[inline]
+    pub fn read_dirty<T: ITrackChanges>(&self, data: &mut Data, obj: &T) -> Result<(), u32> {
+        let offset = data.get_position();
+        let ret = match self.read_delta_mask(data) {
+            Ok(mask) => obj.read_fields(mask, data, self),
+            Err(err) => Err(err),
+        };
+        data.tables.r_ptr_dict.clear();
+        data.r_count = 0;
+        if ret.is_err() {
+            data.set_position(offset);
+        }
+        ret
+    }
+
+    /// 写入 new 相对 old 的差异 old 中的 Rc 字段需要是深拷贝的快照
+    #[inline]
+    pub fn write_delta<T: IDelta>(&self, data: &mut Data, old: &T, new: &T) {
+        data.w_ptr_dict.clear();
+        data.w_count = 0;
+        new.write_delta(old, data, self);
+    }
+
+    /// 读取差异并应用到 target target 需要与写入时的 old 相同
+    /// 失败时恢复读取位置 已应用的字段不恢复
+    #[inline]
+    pub fn apply_delta<T: IDelta>(&self, data: &mut Data, target: &T) -> Result<(), u32> {
+        let offset = data.get_position();
+        let ret = target.apply_delta(data, self);
+        data.tables.r_ptr_dict.clear();
+        data.r_count = 0;
+        if ret.is_err() {
+            data.set_position(offset);
+        }
+        ret
+    }
+
+    /// 用于结构 字段是否改变
+    #[inline]
+    pub fn changed<T: DeltaField>(&self, new: &T, old: &T) -> bool {
+        new.changed_(old, self)
+    }
+
+    /// 用于结构 写入字段差异
+    #[inline]
+    pub fn write_field_delta<T: DeltaField>(&self, data: &mut Data, new: &T, old: &T) {
+        new.write_delta_(old, data, self)
+    }
+
+    /// 用于结构 应用字段差异
+    #[inline]
+    pub fn apply_field_delta<T: DeltaField>(&self, data: &mut Data, v: &T) -> Result<(), u32> {
+        v.apply_delta_(data, self)
+    }
+
+    /// 用于结构 读取变化掩码
+    #[inline]
+    pub fn read_delta_mask(&self, data: &mut Data) -> Result<u64, u32> {
+        match data.read_bit7_u64() {
+            None => Err(line!()),
+            Some((_, mask)) => Ok(mask),
+        }
+    }
+}
+
+/// 读取一个标记
+#[inline]
+fn read_flag(data: &mut Data) -> Result<u8, u32> {
+    if data.remaining() == 0 {
+        Err(line!())
+    } else {
+        Ok(data.get_u8())
+    }
+}
+
+/// 读取长度
+#[inline]
+fn read_len(data: &mut Data) -> Result<usize, u32> {
+    match data.read_bit7_u64() {
+        None => Err(line!()),
+        Some((_, len)) => Ok(len as usize),
+    }
+}
+
+impl<T: IDelta> DeltaValue for T {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        self.changed(old, o)
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        self.write_delta(old, data, o)
+    }
+
+    #[inline]
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        self.apply_delta(data, o)
+    }
+}
+
+impl<T: IDelta> DeltaField for T {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        self.changed(old, o)
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        self.write_delta(old, data, o)
+    }
+
+    #[inline]
+    fn apply_delta_(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        self.apply_delta(data, o)
+    }
+}
+
+impl<T: DeltaValue + Copy> DeltaField for Cell<T> {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        self.get().changed_(&old.get(), o)
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        self.get().write_delta_(&old.get(), data, o)
+    }
+
+    #[inline]
+    fn apply_delta_(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        let mut v = self.get();
+        v.apply_delta_(data, o)?;
+        self.set(v);
+        Ok(())
+    }
+}
+
+impl<T: DeltaValue> DeltaField for RefCell<T> {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        self.borrow().changed_(&old.borrow(), o)
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        self.borrow().write_delta_(&old.borrow(), data, o)
+    }
+
+    #[inline]
+    fn apply_delta_(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        self.borrow_mut().apply_delta_(data, o)
+    }
+}
+
+macro_rules! impl_delta_value {
+    ($($type:ty),+) => {
+        $(
+            impl DeltaValue for $type {
+                #[inline]
+                fn changed_(&self, old: &Self, _: &ObjectManager) -> bool {
+                    self != old
+                }
+
+                #[inline]
+                fn write_delta_(&self, _: &Self, data: &mut Data, o: &ObjectManager) {
+                    self.write_(data, o)
+                }
+
+                #[inline]
+                fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+                    self.read_(data, o)
+                }
+            }
+        )+
+    };
+}
+
+impl_delta_value!(bool, i8, u8, i16, u16, i32, u32, i64, u64, f32, f64, String, Bytes);
+
+/// 0 None, 1 相对默认值的差异, 2 相对旧值的差异
+impl<T: DeltaValue + Default> DeltaValue for Option<T> {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        match (self, old) {
+            (None, None) => false,
+            (Some(v), Some(old)) => v.changed_(old, o),
+            _ => true,
+        }
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        match (self, old) {
+            (None, _) => data.put_u8(0),
+            (Some(v), Some(old)) => {
+                data.put_u8(2);
+                v.write_delta_(old, data, o);
+            }
+            (Some(v), None) => {
+                data.put_u8(1);
+                v.write_delta_(&T::default(), data, o);
+            }
+        }
+    }
+
+    #[inline]
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        match read_flag(data)? {
+            0 => *self = None,
+            1 => {
+                let mut v = T::default();
+                v.apply_delta_(data, o)?;
+                *self = Some(v);
+            }
+            2 => match self {
+                Some(v) => v.apply_delta_(data, o)?,
+                None => return Err(line!()),
+            },
+            _ => return Err(line!()),
+        }
+        Ok(())
+    }
+}
+
+/// 0 None, 1 完整写入, 2 对原对象应用差异
+/// 按内容比较 old 需要是深拷贝的快照(例如 deep_clone) 与 new 共享同一个 Rc 时无法发现原地修改
+impl<T: IDelta + IObjectBase + 'static> DeltaValue for Option<Rc<T>> {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        match (self, old) {
+            (None, None) => false,
+            (Some(v), Some(old)) => v.changed(old, o),
+            _ => true,
+        }
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        match (self, old) {
+            (None, _) => data.put_u8(0),
+            (Some(v), Some(old)) => {
+                data.put_u8(2);
+                v.write_delta(old, data, o);
+            }
+            (Some(_), None) => {
+                data.put_u8(1);
+                self.write_(data, o);
+            }
+        }
+    }
+
+    #[inline]
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        match read_flag(data)? {
+            0 => *self = None,
+            1 => {
+                *self = None;
+                self.read_(data, o)?;
+            }
+            2 => match self {
+                Some(v) => v.apply_delta(data, o)?,
+                None => return Err(line!()),
+            },
+            _ => return Err(line!()),
+        }
+        Ok(())
+    }
+}
+
+/// 按指向判断是否改变 改变时完整写入
+impl<T: IBase + IObjectBase + 'static> DeltaValue for Option<Weak<T>> {
+    #[inline]
+    fn changed_(&self, old: &Self, _: &ObjectManager) -> bool {
+        match (self, old) {
+            (None, None) => false,
+            (Some(v), Some(old)) => !v.ptr_eq(old),
+            _ => true,
+        }
+    }
+
+    #[inline]
+    fn write_delta_(&self, _: &Self, data: &mut Data, o: &ObjectManager) {
+        self.write_(data, o)
+    }
+
+    #[inline]
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        *self = None;
+        self.read_(data, o)
+    }
+}
+
+/// 新长度 + 旧长度内每个元素的标记(0不变 1差异) + 新增元素相对默认值的差异
+impl<T: DeltaValue + Default> DeltaValue for Vec<T> {
+    #[inline]
+    fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+        self.len() != old.len() || self.iter().zip(old.iter()).any(|(v, old)| v.changed_(old, o))
+    }
+
+    #[inline]
+    fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+        data.write_bit7(self.len() as u64);
+        for (i, v) in self.iter().enumerate() {
+            match old.get(i) {
+                Some(old) => {
+                    if v.changed_(old, o) {
+                        data.put_u8(1);
+                        v.write_delta_(old, data, o);
+                    } else {
+                        data.put_u8(0);
+                    }
+                }
+                None => v.write_delta_(&T::default(), data, o),
+            }
+        }
+    }
+
+    #[inline]
+    fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        let len = read_len(data)?;
+        // 每个元素至少一个字节 长度超过剩余数据时不分配
+        if len > data.remaining() {
+            return Err(line!());
+        }
+        let old_len = self.len();
+        self.truncate(len);
+        self.resize_with(len, T::default);
+        for (i, v) in self.iter_mut().enumerate() {
+            if i >= old_len || read_flag(data)? == 1 {
+                v.apply_delta_(data, o)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 删除的键 + 改变的键值(0 相对默认值的差异 1 相对旧值的差异)
+macro_rules! impl_delta_map {
+    ($map:ident, $($bound:path),+) => {
+        impl<K: WriteObject + ReadObject + Default + Clone $(+ $bound)+, V: DeltaValue + Default> DeltaValue for $map<K, V> {
+            #[inline]
+            fn changed_(&self, old: &Self, o: &ObjectManager) -> bool {
+                self.len() != old.len()
+                    || self.iter().any(|(k, v)| match old.get(k) {
+                        Some(old) => v.changed_(old, o),
+                        None => true,
+                    })
+            }
+
+            #[inline]
+            fn write_delta_(&self, old: &Self, data: &mut Data, o: &ObjectManager) {
+                let removed: Vec<&K> = old.keys().filter(|k| !self.contains_key(k)).collect();
+                data.write_bit7(removed.len() as u64);
+                for k in removed {
+                    k.write_(data, o);
+                }
+
+                let changed: Vec<(&K, &V)> = self
+                    .iter()
+                    .filter(|(k, v)| match old.get(k) {
+                        Some(old) => v.changed_(old, o),
+                        None => true,
+                    })
+                    .collect();
+                data.write_bit7(changed.len() as u64);
+                for (k, v) in changed {
+                    k.write_(data, o);
+                    match old.get(k) {
+                        Some(old) => {
+                            data.put_u8(1);
+                            v.write_delta_(old, data, o);
+                        }
+                        None => {
+                            data.put_u8(0);
+                            v.write_delta_(&V::default(), data, o);
+                        }
+                    }
+                }
+            }
+
+            #[inline]
+            fn apply_delta_(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+                for _ in 0..read_len(data)? {
+                    let mut k = K::default();
+                    k.read_(data, o)?;
+                    self.remove(&k);
+                }
+
+                for _ in 0..read_len(data)? {
+                    let mut k = K::default();
+                    k.read_(data, o)?;
+                    if read_flag(data)? == 1 {
+                        match self.get_mut(&k) {
+                            Some(v) => v.apply_delta_(data, o)?,
+                            None => return Err(line!()),
+                        }
+                    } else {
+                        let mut v = V::default();
+                        v.apply_delta_(data, o)?;
+                        self.insert(k, v);
+                    }
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_delta_map!(HashMap, Eq, Hash);
+impl_delta_map!(BTreeMap, Ord);
+
+/// 按字段顺序生成 IDelta 第n个字段使用 field_bit(n) 与 impl_track_changes! 的序号相同
+/// impl_delta_fields!(Type { a, b }) 字段需要实现 DeltaField 超过64个字段编译失败
+#[macro_export]
+macro_rules! impl_delta_fields {
+    ($($ty:ident { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::delta::IDelta for $ty {
+                #[inline]
+                fn changed(&self, old: &Self, o: &$crate::ObjectManager) -> bool {
+                    false $(|| o.changed(&self.$field, &old.$field))*
+                }
+
+                #[inline]
+                fn write_delta(&self, old: &Self, data: &mut $crate::Data, o: &$crate::ObjectManager) {
+                    const FIELDS: &[&str] = &[$(stringify!($field)),*];
+                    let mut mask = 0u64;
+                    $(
+                        if o.changed(&self.$field, &old.$field) {
+                            const BIT: u64 = $crate::delta::field_bit($crate::delta::field_index(FIELDS, stringify!($field)));
+                            mask |= BIT;
+                        }
+                    )*
+                    data.write_bit7(mask);
+                    $(
+                        {
+                            const BIT: u64 = $crate::delta::field_bit($crate::delta::field_index(FIELDS, stringify!($field)));
+                            if mask & BIT != 0 {
+                                o.write_field_delta(data, &self.$field, &old.$field);
+                            }
+                        }
+                    )*
+                }
+
+                #[inline]
+                fn apply_delta(&self, data: &mut $crate::Data, o: &$crate::ObjectManager) -> Result<(), u32> {
+                    const FIELDS: &[&str] = &[$(stringify!($field)),*];
+                    let mask = o.read_delta_mask(data)?;
+                    $(
+                        {
+                            const BIT: u64 = $crate::delta::field_bit($crate::delta::field_index(FIELDS, stringify!($field)));
+                            if mask & BIT != 0 {
+                                o.apply_field_delta(data, &self.$field)?;
+                            }
+                        }
+                    )*
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+/// 按字段顺序生成 ITrackChanges 第n个字段使用 field_bit(n) 与 impl_delta_fields! 的序号相同
+/// impl_track_changes!(Type(dirty) { a, b }) dirty 为 DirtyMask 字段 设置方法使用相同的序号标记脏位
+#[macro_export]
+macro_rules! impl_track_changes {
+    ($($ty:ident($dirty:ident) { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::delta::ITrackChanges for $ty {
+                #[inline]
+                fn dirty_mask(&self) -> &$crate::delta::DirtyMask {
+                    &self.$dirty
+                }
+
+                #[inline]
+                fn write_fields(&self, mask: u64, data: &mut $crate::Data, o: &$crate::ObjectManager) {
+                    const FIELDS: &[&str] = &[$(stringify!($field)),*];
+                    $(
+                        {
+                            const BIT: u64 = $crate::delta::field_bit($crate::delta::field_index(FIELDS, stringify!($field)));
+                            if mask & BIT != 0 {
+                                $crate::object_manager::IObjectManager::write(o, data, &self.$field);
+                            }
+                        }
+                    )*
+                }
+
+                #[inline]
+                fn read_fields(&self, mask: u64, data: &mut $crate::Data, o: &$crate::ObjectManager) -> Result<(), u32> {
+                    const FIELDS: &[&str] = &[$(stringify!($field)),*];
+                    $(
+                        {
+                            const BIT: u64 = $crate::delta::field_bit($crate::delta::field_index(FIELDS, stringify!($field)));
+                            if mask & BIT != 0 {
+                                $crate::object_manager::IObjectManager::read(o, data, &self.$field)?;
+                            }
+                        }
+                    )*
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,68 @@
+use crate::interface::{IBase, IBaseAsRc, IObjectBase};
+use ahash::AHashMap;
+use std::rc::Rc;
+
+type Handler<C> = Box<dyn Fn(Rc<dyn IBase>, &mut C)>;
+
+/// 根据TYPEID 分发 RC<IBASE> 到注册的处理函数
+pub struct Dispatcher<C> {
+    handlers: AHashMap<u32, Vec<Handler<C>>>,
+    fallback: Option<Handler<C>>,
+}
+
+impl<C> Default for Dispatcher<C> {
+    #[inline]
+    fn default() -> Self {
+        Dispatcher {
+            handlers: AHashMap::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl<C> Dispatcher<C> {
+    #[inline]
+    pub fn new() -> Dispatcher<C> {
+        Dispatcher::default()
+    }
+
+    /// 注册 T 的处理函数 同一类型可以注册多个
+    #[inline]
+    pub fn on<T: IObjectBase + 'static, F: Fn(Rc<T>, &mut C) + 'static>(&mut self, f: F) -> &mut Self {
+        self.handlers.entry(T::get_static_full_typeid()).or_default().push(Box::new(move |obj, ctx| {
+            if let Ok(obj) = obj.cast::<T>() {
+                f(obj, ctx)
+            }
+        }));
+        self
+    }
+
+    /// 设置未注册类型的处理函数
+    #[inline]
+    pub fn fallback<F: Fn(Rc<dyn IBase>, &mut C) + 'static>(&mut self, f: F) -> &mut Self {
+        self.fallback = Some(Box::new(f));
+        self
+    }
+
+    /// 是否注册了该完整TYPEID
+    #[inline]
+    pub fn contains(&self, typeid: u32) -> bool {
+        self.handlers.contains_key(&typeid)
+    }
+
+    /// 分发 返回是否有注册的处理函数处理 未注册则调用fallback
+    #[inline]
+    pub fn dispatch(&self, obj: Rc<dyn IBase>, ctx: &mut C) -> bool {
+        if let Some(handlers) = self.handlers.get(&obj.get_full_typeid()) {
+            for handler in handlers {
+                handler(obj.clone(), ctx);
+            }
+            true
+        } else {
+            if let Some(ref fallback) = self.fallback {
+                fallback(obj, ctx);
+            }
+            false
+        }
+    }
+}
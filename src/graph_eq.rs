@@ -0,0 +1,269 @@
+use crate::interface::IBase;
+use ahash::AHashMap;
+use bytes::Bytes;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+/// 结构比较接口 逐字段调用 ctx.field
+/// 实现时在 impl IBase 中使用 graph_eq_dyn! 生成 IBase::graph_eq_dyn 用于比较 Rc<dyn IBase>
+pub trait IGraphEq: IBase {
+    fn graph_eq_fields(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String>;
+}
+
+/// 值的结构比较 不同时返回 "路径: 原因"
+pub trait GraphEq {
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String>;
+}
+
+/// 比较上下文 记录两个图中对象的对应关系和当前路径
+#[derive(Default)]
+pub struct GraphEqContext {
+    pairs: AHashMap<usize, usize>,
+    rev: AHashMap<usize, usize>,
+    path: String,
+}
+
+/// 比较两个对象图 字段值相同 并且共享关系和 Weak 指向同构
+/// 不同时返回第一个不同的路径和原因
+#[inline]
+pub fn graph_eq<T: IGraphEq>(a: &T, b: &T) -> Result<(), String> {
+    a.graph_eq_fields(b, &mut GraphEqContext::default())
+}
+
+impl GraphEqContext {
+    /// 用于结构 比较一个字段
+    #[inline]
+    pub fn field<T: GraphEq>(&mut self, name: &str, a: &T, b: &T) -> Result<(), String> {
+        let len = self.path.len();
+        if len != 0 {
+            self.path.push('.');
+        }
+        self.path.push_str(name);
+        a.graph_eq_(b, self)?;
+        self.path.truncate(len);
+        Ok(())
+    }
+
+    /// 比较一个元素
+    #[inline]
+    fn item<K: Debug, T: GraphEq>(&mut self, key: &K, a: &T, b: &T) -> Result<(), String> {
+        let len = self.path.len();
+        self.path.push_str(&format!("[{:?}]", key));
+        a.graph_eq_(b, self)?;
+        self.path.truncate(len);
+        Ok(())
+    }
+
+    /// 生成当前路径的错误
+    #[inline]
+    pub fn diff(&self, reason: &str) -> String {
+        format!("{}: {}", self.path, reason)
+    }
+
+    /// 记录两个对象的对应关系 已比较过返回false 对应关系冲突返回错误
+    #[inline]
+    fn pair(&mut self, a: usize, b: usize) -> Result<bool, String> {
+        match (self.pairs.get(&a), self.rev.get(&b)) {
+            (None, None) => {
+                self.pairs.insert(a, b);
+                self.rev.insert(b, a);
+                Ok(true)
+            }
+            (Some(&x), _) if x == b => Ok(false),
+            _ => Err(self.diff("shared reference differs")),
+        }
+    }
+}
+
+impl<T: IGraphEq> GraphEq for T {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        self.graph_eq_fields(other, ctx)
+    }
+}
+
+macro_rules! impl_graph_eq {
+    ($($type:ty),+) => {
+        $(
+            impl GraphEq for $type {
+                #[inline]
+                fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+                    if self == other {
+                        Ok(())
+                    } else {
+                        Err(ctx.diff(&format!("{:?} != {:?}", self, other)))
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_graph_eq!(bool, i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, String, Bytes);
+
+/// 浮点数 NaN 与 NaN 相同
+macro_rules! impl_graph_eq_float {
+    ($($type:ty),+) => {
+        $(
+            impl GraphEq for $type {
+                #[inline]
+                fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+                    if self == other || (self.is_nan() && other.is_nan()) {
+                        Ok(())
+                    } else {
+                        Err(ctx.diff(&format!("{:?} != {:?}", self, other)))
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_graph_eq_float!(f32, f64);
+
+impl<T: GraphEq + Copy> GraphEq for Cell<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        self.get().graph_eq_(&other.get(), ctx)
+    }
+}
+
+impl<T: GraphEq> GraphEq for RefCell<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        self.borrow().graph_eq_(&other.borrow(), ctx)
+    }
+}
+
+impl<T: GraphEq> GraphEq for Option<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        match (self, other) {
+            (None, None) => Ok(()),
+            (Some(a), Some(b)) => a.graph_eq_(b, ctx),
+            (None, Some(_)) => Err(ctx.diff("None != Some")),
+            (Some(_), None) => Err(ctx.diff("Some != None")),
+        }
+    }
+}
+
+impl<T: IGraphEq> GraphEq for Rc<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        let a = &**self as *const T as *const u8 as usize;
+        let b = &**other as *const T as *const u8 as usize;
+        if ctx.pair(a, b)? {
+            self.graph_eq_fields(other, ctx)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 比较TYPEID 再通过 IBase::graph_eq_dyn 比较字段 共享关系与外层使用同一个对应表
+/// 没有实现 IGraphEq 的类型返回错误
+impl GraphEq for Rc<dyn IBase> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        let a = &**self as *const dyn IBase as *const u8 as usize;
+        let b = &**other as *const dyn IBase as *const u8 as usize;
+        if !ctx.pair(a, b)? {
+            return Ok(());
+        }
+        if self.get_full_typeid() != other.get_full_typeid() {
+            return Err(ctx.diff(&format!("typeid {} != {}", self.get_full_typeid(), other.get_full_typeid())));
+        }
+        match self.graph_eq_dyn(&**other, ctx) {
+            Some(ret) => ret,
+            None => Err(ctx.diff(&format!("graph_eq not supported for typeid {}", self.get_full_typeid()))),
+        }
+    }
+}
+
+impl<T: IGraphEq> GraphEq for Weak<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        match (self.upgrade(), other.upgrade()) {
+            (None, None) => Ok(()),
+            (Some(a), Some(b)) => a.graph_eq_(&b, ctx),
+            _ => Err(ctx.diff("weak target differs")),
+        }
+    }
+}
+
+impl<T: GraphEq> GraphEq for Vec<T> {
+    #[inline]
+    fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+        if self.len() != other.len() {
+            return Err(ctx.diff(&format!("len {} != {}", self.len(), other.len())));
+        }
+        for (i, (a, b)) in self.iter().zip(other.iter()).enumerate() {
+            ctx.item(&i, a, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按键排序比较 第一个不同的路径与遍历顺序无关
+macro_rules! impl_graph_eq_map {
+    ($map:ident $(, $bound:path)*) => {
+        impl<K: Debug + Ord $(+ $bound)*, V: GraphEq> GraphEq for $map<K, V> {
+            #[inline]
+            fn graph_eq_(&self, other: &Self, ctx: &mut GraphEqContext) -> Result<(), String> {
+                if self.len() != other.len() {
+                    return Err(ctx.diff(&format!("len {} != {}", self.len(), other.len())));
+                }
+                let mut keys: Vec<&K> = self.keys().collect();
+                keys.sort();
+                for k in keys {
+                    match other.get(k) {
+                        Some(b) => ctx.item(k, &self[k], b)?,
+                        None => return Err(ctx.diff(&format!("missing key {:?}", k))),
+                    }
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_graph_eq_map!(HashMap, Hash);
+impl_graph_eq_map!(BTreeMap);
+
+/// 在 impl IBase 中生成 graph_eq_dyn 通过 IGraphEq 比较 类型没有实现 IGraphEq 时编译失败
+#[macro_export]
+macro_rules! graph_eq_dyn {
+    () => {
+        #[inline]
+        fn graph_eq_dyn(
+            &self,
+            other: &dyn $crate::interface::IBase,
+            ctx: &mut $crate::graph_eq::GraphEqContext,
+        ) -> Option<Result<(), String>> {
+            Some(match other.as_any().downcast_ref::<Self>() {
+                Some(other) => $crate::graph_eq::IGraphEq::graph_eq_fields(self, other, ctx),
+                None => Err(ctx.diff("type differs")),
+            })
+        }
+    };
+}
+
+/// 按字段顺序生成 IGraphEq 路径使用字段名
+/// impl_graph_eq_fields!(Type { a, b }) 字段需要实现 GraphEq
+#[macro_export]
+macro_rules! impl_graph_eq_fields {
+    ($($ty:ident { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::graph_eq::IGraphEq for $ty {
+                #[inline]
+                fn graph_eq_fields(&self, other: &Self, ctx: &mut $crate::graph_eq::GraphEqContext) -> Result<(), String> {
+                    $(ctx.field(stringify!($field), &self.$field, &other.$field)?;)*
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
@@ -1,20 +1,118 @@
+use crate::deep_clone::CloneMap;
+use crate::graph_eq::GraphEqContext;
+use crate::reflect::Reflect;
 use crate::{Data, ObjectManager};
+use std::any::Any;
 use std::cell::RefCell;
 use std::fmt::Debug;
-use std::mem::forget;
 use std::ops::Deref;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+
+/// 组合命名空间和TYPEID 命名空间0的完整TYPEID与原TYPEID相同 写入格式不变
+#[inline]
+pub const fn full_typeid(namespace: u16, typeid: u16) -> u32 {
+    (namespace as u32) << 16 | typeid as u32
+}
+
+/// 根据名称生成稳定的命名空间 FNV-1a 折叠为16位 不会返回0
+pub const fn namespace_of(name: &str) -> u16 {
+    let bytes = name.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    let ns = ((hash >> 16) ^ (hash & 0xffff)) as u16;
+    if ns == 0 {
+        1
+    } else {
+        ns
+    }
+}
+
+/// 转换为 Any 所有 IBase 类型自动实现 用于安全的向下转换
+/// 只为 IBase 实现 避免 Rc<T> 等包装类型自身被转换
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any_rc(self: Rc<Self>) -> Rc<dyn Any>;
+}
+
+impl<T: IBase> AsAny for T {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any_rc(self: Rc<Self>) -> Rc<dyn Any> {
+        self
+    }
+}
 
 /// Base接口
-pub trait IBase: Debug {
+pub trait IBase: Debug + AsAny {
     fn write(&self, data: &mut Data, o: &ObjectManager);
     fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
     fn get_typeid(&self) -> u16;
+
+    /// 命名空间 默认0 与TYPEID组成32位完整TYPEID
+    /// 需要与 IObjectBase::get_static_namespace 一致 调试时 register 会检查
+    #[inline]
+    fn get_namespace(&self) -> u16 {
+        0
+    }
+
+    /// 完整TYPEID (namespace<<16)|typeid
+    #[inline]
+    fn get_full_typeid(&self) -> u32 {
+        full_typeid(self.get_namespace(), self.get_typeid())
+    }
+
+    /// 内存中深度复制 实现了 IDeepClone 的类型使用 deep_clone_dyn! 生成 默认不支持
+    #[inline]
+    fn deep_clone_dyn(&self, _: &mut CloneMap) -> Option<Rc<dyn IBase>> {
+        None
+    }
+
+    /// 结构比较 实现了 IGraphEq 的类型使用 graph_eq_dyn! 生成 默认不支持
+    #[inline]
+    fn graph_eq_dyn(&self, _: &dyn IBase, _: &mut GraphEqContext) -> Option<Result<(), String>> {
+        None
+    }
+
+    /// 反射 实现了 Reflect 的类型返回 Some(self) 默认不支持
+    #[inline]
+    fn as_reflect(&self) -> Option<&dyn Reflect> {
+        None
+    }
+}
+
+/// 独占对象的读取写入接口 字段不需要 Cell/RefCell
+/// 用于顶层值和 Box 子对象 共享的 Rc 对象仍使用 IBase
+/// 实现后使用 impl_base_mut! 生成 ReadObject/WriteObject 按字段顺序读写时 impl_base_mut!(Type { a, b }) 同时生成实现
+pub trait IBaseMut {
+    fn write_mut(&self, data: &mut Data, o: &ObjectManager);
+    fn read_mut(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
 }
 
 /// OBJECT BASE 接口
 pub trait IObjectBase: IBase + Default {
     fn get_static_typeid() -> u16;
+
+    /// 命名空间 默认0 需要与 IBase::get_namespace 一致
+    #[inline]
+    fn get_static_namespace() -> u16 {
+        0
+    }
+
+    /// 完整TYPEID (namespace<<16)|typeid
+    #[inline]
+    fn get_static_full_typeid() -> u32 {
+        full_typeid(Self::get_static_namespace(), Self::get_static_typeid())
+    }
+
     fn new() -> Option<Rc<dyn IBase>>;
 }
 
@@ -25,18 +123,48 @@ pub trait IBaseAsRc {
 }
 
 impl IBaseAsRc for Rc<dyn IBase> {
+    /// 按实际类型检查后转换 TYPEID 相同但类型不同时返回原值
     #[inline]
     fn cast<T: IObjectBase>(self) -> Result<Rc<T>, Self> {
-        if self.get_typeid() != T::get_static_typeid() {
-            Err(self)
+        if (*self).as_any().is::<T>() {
+            match self.into_any_rc().downcast::<T>() {
+                Ok(v) => Ok(v),
+                Err(_) => unreachable!(),
+            }
         } else {
-            let ptr = &self as *const Rc<dyn IBase> as *const Rc<T>;
-            forget(self);
-            unsafe { Ok(ptr.read()) }
+            Err(self)
+        }
+    }
+}
+
+pub trait IBaseAsWeak {
+    fn cast_weak<T: IObjectBase>(self) -> Result<Weak<T>, Self>
+    where
+        Self: Sized;
+}
+
+impl IBaseAsWeak for Weak<dyn IBase> {
+    /// 升级后按实际类型转换 类型不同时返回原值 对象已释放时返回 Weak::new()
+    #[inline]
+    fn cast_weak<T: IObjectBase>(self) -> Result<Weak<T>, Self> {
+        match self.upgrade() {
+            Some(ptr) => match ptr.cast::<T>() {
+                Ok(v) => Ok(Rc::downgrade(&v)),
+                Err(_) => Err(self),
+            },
+            None => Ok(Weak::new()),
         }
     }
 }
 
+impl dyn IBase {
+    /// 引用转换为具体类型 类型不同时返回None
+    #[inline]
+    pub fn cast_ref<T: IObjectBase>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+}
+
 /// 读取简化接口
 pub trait GetValue {
     type ReturnType;
@@ -99,3 +227,58 @@ impl<T: Clone> GetValue for RefCell<Option<T>> {
         None
     }
 }
+
+/// 为实现了 IBaseMut 的类型和它的 Box 生成 ReadObject/WriteObject
+/// impl_base_mut!(Type { a, b }) 按字段顺序生成 IBaseMut 后再生成 ReadObject/WriteObject
+#[macro_export]
+macro_rules! impl_base_mut {
+    ($($ty:ident { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::interface::IBaseMut for $ty {
+                #[inline]
+                fn write_mut(&self, data: &mut $crate::Data, o: &$crate::ObjectManager) {
+                    $($crate::object_manager::IObjectManager::write(o, data, &self.$field);)*
+                }
+
+                #[inline]
+                fn read_mut(&mut self, data: &mut $crate::Data, o: &$crate::ObjectManager) -> Result<(), u32> {
+                    $(o.read_mut(data, &mut self.$field)?;)*
+                    Ok(())
+                }
+            }
+
+            $crate::impl_base_mut!($ty);
+        )+
+    };
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::object_manager::ReadObject for $ty {
+                #[inline]
+                fn read_(&mut self, data: &mut $crate::Data, obj_manager: &$crate::ObjectManager) -> Result<(), u32> {
+                    $crate::interface::IBaseMut::read_mut(self, data, obj_manager)
+                }
+            }
+
+            impl $crate::object_manager::WriteObject for $ty {
+                #[inline]
+                fn write_(&self, data: &mut $crate::Data, obj_manager: &$crate::ObjectManager) {
+                    $crate::interface::IBaseMut::write_mut(self, data, obj_manager)
+                }
+            }
+
+            impl $crate::object_manager::ReadObject for Box<$ty> {
+                #[inline]
+                fn read_(&mut self, data: &mut $crate::Data, obj_manager: &$crate::ObjectManager) -> Result<(), u32> {
+                    $crate::interface::IBaseMut::read_mut(&mut **self, data, obj_manager)
+                }
+            }
+
+            impl $crate::object_manager::WriteObject for Box<$ty> {
+                #[inline]
+                fn write_(&self, data: &mut $crate::Data, obj_manager: &$crate::ObjectManager) {
+                    $crate::interface::IBaseMut::write_mut(&**self, data, obj_manager)
+                }
+            }
+        )+
+    };
+}
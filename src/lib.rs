@@ -1,9 +1,38 @@
+#[macro_use]
+mod manager;
+
 pub mod data;
+pub mod data_ref;
+pub mod decoded_graph;
+pub mod deep_clone;
+pub mod delta;
+pub mod dispatcher;
+pub mod graph_eq;
 pub mod interface;
 pub mod object_manager;
+pub mod reflect;
+pub mod registry;
+pub mod session;
+pub mod sync;
+#[cfg(feature = "async")]
+pub mod codec;
+#[cfg(feature = "async")]
+pub mod rpc;
 
 pub use data::*;
+pub use data_ref::*;
+pub use decoded_graph::*;
+pub use deep_clone::*;
+pub use delta::*;
+pub use dispatcher::*;
+pub use graph_eq::*;
 pub use interface::*;
 pub use object_manager::*;
+pub use reflect::*;
+pub use registry::*;
+pub use session::*;
+#[cfg(feature = "async")]
+pub use codec::*;
 
+pub use inventory;
 pub use pkgbuilder;
@@ -0,0 +1,1085 @@
+/// RC 和线程安全模型共用的 ObjectManager 实现
+/// 调用处需要引入该模型的 Data IBase IObjectBase IBaseAs* 指针类型和 Weak
+/// ptr 为 Rc 或 Arc extends_ptr 为按指针转换注册继承的方法名 send 为工厂等闭包需要的额外约束
+macro_rules! impl_object_manager {
+    ($ptr:ident, $extends_ptr:ident, [$($send:tt)*]) => {
+        /// object 读取写入接口
+        pub trait IObjectManager {
+            fn write_to<T: WriteObject>(&self, data: &mut Data, arg: &T);
+            fn write<T: WriteObject>(&self, data: &mut Data, arg: &T);
+            fn write_core<T: IBase + ?Sized>(&self, data: &mut Data, arg: &$ptr<T>);
+            fn read_from<T: ReadObject>(&self, data: &mut Data, arg: &mut T) -> Result<(), u32>;
+            fn read<T: ReadOnlyObject>(&self, data: &mut Data, arg: &T) -> Result<(), u32>;
+            fn read_core(&self, data: &mut Data) -> Result<$ptr<dyn IBase>, u32>;
+        }
+
+        /// 对象工厂 参数为读取时传入的上下文
+        pub type Factory = Box<dyn Fn(Option<&dyn Any>) -> Option<$ptr<dyn IBase>> $($send)*>;
+
+        /// 旧对象迁移为新对象 类型不符时返回None
+        type Migrate = Box<dyn Fn($ptr<dyn IBase>) -> Option<$ptr<dyn IBase>> $($send)*>;
+
+        /// 旧TYPEID 别名 按旧结构读取后迁移为新类型
+        struct Alias {
+            typeid: u32,
+            create: fn() -> Option<$ptr<dyn IBase>>,
+            migrate: Migrate,
+        }
+
+        pub struct ObjectManager {
+            fs: AHashMap<u32, Factory>,
+            names: AHashMap<u32, (&'static str, TypeId)>,
+            aliases: AHashMap<u32, Alias>,
+            upcasts: AHashMap<(TypeId, u32), Box<dyn Any $($send)*>>,
+            parents: AHashMap<u32, Parent>,
+        }
+
+        /// 子类型到基类型的视图
+        type View = Box<dyn for<'a> Fn(&'a (dyn IBase + 'static)) -> Option<&'a (dyn IBase + 'static)> $($send)*>;
+
+        /// 子类型指针转换为父类型指针
+        type ParentUpcast = Box<dyn Fn($ptr<dyn IBase>) -> Option<$ptr<dyn IBase>> $($send)*>;
+
+        /// 继承关系 父类型TYPEID 子类型到父类型的视图 和指针转换 两者至少有一个
+        struct Parent {
+            typeid: u32,
+            view: Option<View>,
+            upcast: Option<ParentUpcast>,
+        }
+
+        type Upcast<Tr> = Box<dyn Fn($ptr<dyn IBase>) -> Option<$ptr<Tr>> $($send)*>;
+
+        /// 调试时检查实例与类型的完整TYPEID一致 get_namespace 需要与 get_static_namespace 相同
+        #[inline]
+        fn debug_assert_typeid<T: IObjectBase>() {
+            if cfg!(debug_assertions) {
+                if let Some(v) = T::new() {
+                    assert_eq!(
+                        T::get_static_full_typeid(),
+                        v.get_full_typeid(),
+                        "{}: get_namespace/get_typeid disagree with get_static_namespace/get_static_typeid",
+                        std::any::type_name::<T>()
+                    );
+                }
+            }
+        }
+
+        impl IObjectManager for ObjectManager {
+            /// 写入
+            #[inline]
+            fn write_to<T: WriteObject>(&self, data: &mut Data, arg: &T) {
+                data.w_ptr_dict.clear();
+                data.w_count = 0;
+                arg.write_(data, self);
+            }
+
+            /// 写入结构字段用
+            #[inline]
+            fn write<T: WriteObject>(&self, data: &mut Data, arg: &T) {
+                arg.write_(data, self);
+            }
+
+            /// 写入一个对象
+            #[inline]
+            fn write_core<T: IBase + ?Sized>(&self, data: &mut Data, arg: &$ptr<T>) {
+                data.w_ptr_dict.clear();
+                data.w_count = 0;
+                self.write_ptr(data, arg);
+            }
+
+            /// 读取到 arg 失败时恢复读取位置
+            /// 不是原子的 失败时 arg 中已读取的部分不恢复 需要原子读取时使用 read_from_atomic
+            #[inline]
+            fn read_from<T: ReadObject>(&self, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+                self.read_in_place(data, arg)
+            }
+
+            /// 用于结构读取
+            #[inline]
+            fn read<T: ReadOnlyObject>(&self, data: &mut Data, arg: &T) -> Result<(), u32> {
+                arg.read_(data, self)
+            }
+
+            /// 根据DATA 数据读取一个对象 失败时恢复读取位置
+            #[inline]
+            fn read_core(&self, data: &mut Data) -> Result<$ptr<dyn IBase>, u32> {
+                let mut value: Option<$ptr<dyn IBase>> = None;
+                self.read_from(data, &mut value)?;
+                match value {
+                    None => Err(line!()),
+                    Some(p) => Ok(p),
+                }
+            }
+        }
+
+        impl Default for ObjectManager {
+            #[inline]
+            fn default() -> Self {
+                ObjectManager {
+                    fs: AHashMap::new(),
+                    names: AHashMap::new(),
+                    aliases: AHashMap::new(),
+                    upcasts: AHashMap::new(),
+                    parents: AHashMap::new(),
+                }
+            }
+        }
+
+        impl ObjectManager {
+            #[inline]
+            pub fn new() -> ObjectManager {
+                ObjectManager::default()
+            }
+
+            /// 注册PKG TYPEID 已被其他类型注册时 panic
+            #[inline]
+            pub fn register<T: IObjectBase + 'static>(&mut self) {
+                if let Err(err) = self.try_register::<T>() {
+                    panic!("{}", err)
+                }
+            }
+
+            /// 注册PKG TYPEID 已被其他类型注册时 返回错误
+            #[inline]
+            pub fn try_register<T: IObjectBase + 'static>(&mut self) -> Result<(), String> {
+                debug_assert_typeid::<T>();
+                self.insert_factory(T::get_static_full_typeid(), std::any::type_name::<T>(), TypeId::of::<T>(), Box::new(|_| T::new()))
+            }
+
+            /// 注册带上下文的工厂 通过 read_core_with/read_from_with 传入上下文
+            /// 读取时没有传入上下文或上下文类型不是 C 则读取失败
+            /// 工厂返回对象的完整TYPEID 与 typeid 不一致时创建失败 调试时 panic
+            /// TYPEID 已被其他类型或其他工厂注册时 panic
+            #[inline]
+            pub fn register_with<C: Any, F: Fn(&C) -> $ptr<dyn IBase> $($send)* + 'static>(&mut self, typeid: u32, f: F) {
+                let factory: Factory = Box::new(move |ctx| {
+                    let v = f(ctx?.downcast_ref::<C>()?);
+                    debug_assert_eq!(typeid, v.get_full_typeid(), "register_with factory returned wrong typeid");
+                    if v.get_full_typeid() == typeid {
+                        Some(v)
+                    } else {
+                        None
+                    }
+                });
+                if let Err(err) = self.insert_factory(typeid, std::any::type_name::<F>(), TypeId::of::<F>(), factory) {
+                    panic!("{}", err)
+                }
+            }
+
+            /// 注册 T 并记录 T 到用户 trait object Tr 的转换 例如 register_as::<Circle, dyn Shape>(|v| v)
+            /// 之后可用 read_dyn 读取 Option<Rc<Tr>> 等字段
+            #[inline]
+            pub fn register_as<T: IObjectBase + 'static, Tr: ?Sized + 'static>(&mut self, upcast: fn($ptr<T>) -> $ptr<Tr>) {
+                self.register::<T>();
+                let f: Upcast<Tr> = Box::new(move |obj| obj.cast::<T>().ok().map(upcast));
+                self.upcasts.insert((TypeId::of::<Tr>(), T::get_static_full_typeid()), Box::new(f));
+            }
+
+            /// 对象转换为 Tr 该TYPEID 没有通过 register_as 注册 Tr 时返回原值
+            #[inline]
+            pub fn upcast<Tr: ?Sized + 'static>(&self, obj: $ptr<dyn IBase>) -> Result<$ptr<Tr>, $ptr<dyn IBase>> {
+                let f = self.upcasts.get(&(TypeId::of::<Tr>(), obj.get_full_typeid())).and_then(|f| f.downcast_ref::<Upcast<Tr>>());
+                match f {
+                    Some(f) => f(obj.clone()).ok_or(obj),
+                    None => Err(obj),
+                }
+            }
+
+            /// 用于结构读取 读取一个 Tr 对象 Tr 为用户 trait object 读取到的类型没有实现 Tr 时返回错误
+            #[inline]
+            pub fn read_dyn<Tr: ?Sized + 'static>(&self, data: &mut Data, v: &mut Option<$ptr<Tr>>) -> Result<(), u32> {
+                let mut obj: Option<$ptr<dyn IBase>> = None;
+                self.read_ptr_ibase(data, &mut obj)?;
+                match obj {
+                    None => {
+                        *v = None;
+                        Ok(())
+                    }
+                    Some(obj) => match self.upcast::<Tr>(obj) {
+                        Ok(obj) => {
+                            *v = Some(obj);
+                            Ok(())
+                        }
+                        Err(_) => Err(line!()),
+                    },
+                }
+            }
+
+            /// 注册 Sub 继承 Base 并注册两个类型 view 返回子类型内嵌的基类型
+            /// 之后 Sub 可以读取到期望 Base 的 IBase 字段 并通过 view 得到基类型视图
+            /// 形成循环继承时 panic
+            #[inline]
+            pub fn extends<Sub: IObjectBase + 'static, Base: IObjectBase + 'static>(&mut self, view: fn(&Sub) -> &Base) {
+                let view: View = Box::new(move |obj| obj.cast_ref::<Sub>().map(|v| view(v) as &dyn IBase));
+                self.insert_parent::<Sub, Base>(Some(view), None);
+            }
+
+            /// 注册 Sub 继承 Base upcast 返回子类型对应的基类型对象
+            /// 之后 Sub 可以读取到 Base 指针字段 引用表中保留 Sub 每次引用都调用 upcast
+            /// 基类型在 RefCell/Mutex 中等无法返回引用的位置时使用 此时 view 不经过该层
+            #[inline]
+            pub fn $extends_ptr<Sub: IObjectBase + 'static, Base: IObjectBase + 'static>(&mut self, upcast: fn($ptr<Sub>) -> $ptr<Base>) {
+                let upcast: ParentUpcast = Box::new(move |obj| obj.cast::<Sub>().ok().map(|v| upcast(v) as $ptr<dyn IBase>));
+                self.insert_parent::<Sub, Base>(None, Some(upcast));
+            }
+
+            #[inline]
+            fn insert_parent<Sub: IObjectBase + 'static, Base: IObjectBase + 'static>(&mut self, view: Option<View>, upcast: Option<ParentUpcast>) {
+                let typeid = Sub::get_static_full_typeid();
+                let base = Base::get_static_full_typeid();
+                if self.is_kind_of(base, typeid) {
+                    panic!("typeid {} extends {} is cyclic", typeid, base);
+                }
+                self.register::<Sub>();
+                self.register::<Base>();
+                self.parents.insert(typeid, Parent { typeid: base, view, upcast });
+            }
+
+            /// 沿继承链把对象转换为 T 链上每一级都需要通过指针转换注册
+            #[inline]
+            fn upcast_to<T: IObjectBase + 'static>(&self, mut obj: $ptr<dyn IBase>) -> Option<$ptr<T>> {
+                loop {
+                    match obj.cast::<T>() {
+                        Ok(v) => return Some(v),
+                        Err(o) => obj = o,
+                    }
+                    let upcast = self.parents.get(&obj.get_full_typeid())?.upcast.as_ref()?;
+                    obj = upcast(obj)?;
+                }
+            }
+
+            /// typeid 是否为 base 或者继承自 base
+            #[inline]
+            pub fn is_kind_of(&self, mut typeid: u32, base: u32) -> bool {
+                loop {
+                    if typeid == base {
+                        return true;
+                    }
+                    match self.parents.get(&typeid) {
+                        Some(parent) => typeid = parent.typeid,
+                        None => return false,
+                    }
+                }
+            }
+
+            /// 返回对象的 B 类型视图 对象为 B 或继承自 B
+            #[inline]
+            pub fn view<'a, B: IObjectBase + 'static>(&self, mut obj: &'a dyn IBase) -> Option<&'a B> {
+                loop {
+                    if let Some(v) = obj.cast_ref::<B>() {
+                        return Some(v);
+                    }
+                    obj = (self.parents.get(&obj.get_full_typeid())?.view.as_ref()?)(obj)?;
+                }
+            }
+
+            /// 用于结构读取 读取一个 B 或继承自 B 的对象 保留实际类型
+            #[inline]
+            pub fn read_kind_of<B: IObjectBase + 'static>(&self, data: &mut Data, v: &mut Option<$ptr<dyn IBase>>) -> Result<(), u32> {
+                let mut obj: Option<$ptr<dyn IBase>> = None;
+                self.read_ptr_ibase(data, &mut obj)?;
+                if let Some(ref obj) = obj {
+                    if !self.is_kind_of(obj.get_full_typeid(), B::get_static_full_typeid()) {
+                        return Err(line!());
+                    }
+                }
+                *v = obj;
+                Ok(())
+            }
+
+            /// 注册旧结构 Old 的TYPEID 为别名 读取到该TYPEID 时按 Old 读取 再通过 migrate 转换为 new_typeid 的新类型
+            /// 旧对象内部对自身的循环引用 读取后仍指向旧对象
+            /// Old 的TYPEID 已被注册为普通类型或别名时 panic
+            #[inline]
+            pub fn alias<Old: IObjectBase + 'static, F: Fn(&Old) -> $ptr<dyn IBase> $($send)* + 'static>(&mut self, new_typeid: u32, migrate: F) {
+                if let Err(err) = self.try_alias(new_typeid, migrate) {
+                    panic!("{}", err)
+                }
+            }
+
+            /// 注册旧结构 Old 的TYPEID 为别名 已被注册为普通类型或别名时 返回错误
+            #[inline]
+            pub fn try_alias<Old: IObjectBase + 'static, F: Fn(&Old) -> $ptr<dyn IBase> $($send)* + 'static>(
+                &mut self, new_typeid: u32, migrate: F,
+            ) -> Result<(), String> {
+                let old_typeid = Old::get_static_full_typeid();
+                let name = std::any::type_name::<Old>();
+                if let Some((exist, _)) = self.names.get(&old_typeid) {
+                    return Err(format!("typeid {} conflict: {} and alias {}", old_typeid, exist, name));
+                }
+                if self.aliases.contains_key(&old_typeid) {
+                    return Err(format!("typeid {} conflict: alias already registered for {}", old_typeid, name));
+                }
+                self.aliases.insert(
+                    old_typeid,
+                    Alias {
+                        typeid: new_typeid,
+                        create: Old::new,
+                        migrate: Box::new(move |old| old.cast::<Old>().ok().map(|old| migrate(&old))),
+                    },
+                );
+                Ok(())
+            }
+
+            /// 是否为别名TYPEID
+            #[inline]
+            pub fn is_alias(&self, typeid: u32) -> bool {
+                self.aliases.contains_key(&typeid)
+            }
+
+            #[inline]
+            fn insert_factory(&mut self, typeid: u32, name: &'static str, id: TypeId, factory: Factory) -> Result<(), String> {
+                if self.aliases.contains_key(&typeid) {
+                    return Err(format!("typeid {} conflict: alias and {}", typeid, name));
+                }
+                // 按 TypeId 比较 同一函数中的闭包类型名相同
+                if let Some((exist, exist_id)) = self.names.get(&typeid) {
+                    if *exist_id != id {
+                        return Err(format!("typeid {} conflict: {} and {}", typeid, exist, name));
+                    }
+                }
+                self.names.insert(typeid, (name, id));
+                self.fs.insert(typeid, factory);
+                Ok(())
+            }
+
+            /// 所有已注册的 (TYPEID,类型名) 按TYPEID排序
+            #[inline]
+            pub fn registered(&self) -> Vec<(u32, &'static str)> {
+                let mut ids: Vec<(u32, &'static str)> = self.names.iter().map(|(k, (v, _))| (*k, *v)).collect();
+                ids.sort_unstable();
+                ids
+            }
+
+            /// 是否注册了该完整TYPEID
+            #[inline]
+            pub fn is_registered(&self, typeid: u32) -> bool {
+                self.fs.contains_key(&typeid)
+            }
+
+            /// 根据完整TYPEID 返回 对象
+            #[inline]
+            pub fn create(&self, typeid: u32) -> Option<$ptr<dyn IBase>> {
+                self.create_with(typeid, None)
+            }
+
+            /// 根据完整TYPEID 和上下文 返回 对象
+            #[inline]
+            pub fn create_with(&self, typeid: u32, ctx: Option<&dyn Any>) -> Option<$ptr<dyn IBase>> {
+                match self.fs.get(&typeid) {
+                    Some(f) => f(ctx),
+                    None => None,
+                }
+            }
+
+            /// 读取时使用 data 中的上下文创建对象
+            #[inline]
+            fn create_in(&self, typeid: u32, data: &Data) -> Option<$ptr<dyn IBase>> {
+                match data.tables.ctx {
+                    Some(ref ctx) => self.create_with(typeid, Some(&**ctx)),
+                    None => self.create_with(typeid, None),
+                }
+            }
+
+            /// 带上下文读取一个对象 上下文传给 register_with 注册的工厂
+            #[inline]
+            pub fn read_core_with(&self, data: &mut Data, ctx: $ptr<dyn Any $($send)*>) -> Result<$ptr<dyn IBase>, u32> {
+                let old = data.tables.ctx.replace(ctx);
+                let ret = self.read_core(data);
+                data.tables.ctx = old;
+                ret
+            }
+
+            /// 带上下文读取一个预设值
+            #[inline]
+            pub fn read_from_with<T: ReadObject>(&self, data: &mut Data, arg: &mut T, ctx: $ptr<dyn Any $($send)*>) -> Result<(), u32> {
+                let old = data.tables.ctx.replace(ctx);
+                let ret = self.read_from(data, arg);
+                data.tables.ctx = old;
+                ret
+            }
+
+            /// 读取到新创建的值 成功后替换 arg 失败时 arg 和读取位置不变
+            #[inline]
+            pub fn read_from_atomic<T: ReadObject + Default>(&self, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+                let mut value = T::default();
+                self.read_in_place(data, &mut value)?;
+                *arg = value;
+                Ok(())
+            }
+
+            /// 按指定方式读取到已有的值 失败时恢复读取位置
+            /// 不是原子的 失败时 arg 中已读取的部分不恢复 需要原子读取时使用 read_from_atomic
+            #[inline]
+            pub fn read_from_mode<T: ReadObject>(&self, data: &mut Data, arg: &mut T, mode: $crate::ReadMode) -> Result<(), u32> {
+                let old = data.read_mode;
+                data.read_mode = mode;
+                let ret = self.read_in_place(data, arg);
+                data.read_mode = old;
+                ret
+            }
+
+            /// 读取到 arg 结束后清空引用表 失败时恢复读取位置
+            #[inline]
+            fn read_in_place<T: ReadObject>(&self, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+                let offset = data.get_position();
+                let ret = arg.read_(data, self);
+                data.tables.r_ptr_dict.clear();
+                data.r_count = 0;
+                if ret.is_err() {
+                    data.set_position(offset);
+                }
+                ret
+            }
+
+            /// 写入到 io::Write, 格式为 bit7长度+数据
+            /// 长度在数据之前 对象先编码到一个缓冲区 帧头填入预留位置后一次写出 不再复制
+            #[inline]
+            pub fn write_into<W: std::io::Write, T: WriteObject>(&self, writer: &mut W, arg: &T) -> std::io::Result<()> {
+                let mut data = Data::with_frame_head();
+                self.write_to(&mut data, arg);
+                writer.write_all(data.frame())
+            }
+
+            /// 从 io::Read 读取一个 write_into 写入的对象 长度超过 MAX_FRAME_LEN 返回 InvalidData
+            #[inline]
+            pub fn read_out_of<R: std::io::Read, T: ReadObject>(&self, reader: &mut R, arg: &mut T) -> std::io::Result<()> {
+                self.read_out_of_max(reader, arg, $crate::MAX_FRAME_LEN)
+            }
+
+            /// 从 io::Read 读取一个 write_into 写入的对象 长度超过 max_len 返回 InvalidData
+            #[inline]
+            pub fn read_out_of_max<R: std::io::Read, T: ReadObject>(&self, reader: &mut R, arg: &mut T, max_len: usize) -> std::io::Result<()> {
+                let mut data = Data::read_frame_io(reader, max_len)?;
+                self.read_from(&mut data, arg).map_err($crate::object_manager::read_error)
+            }
+
+            /// 写入对象 同一对象只写入一次 之后写入编号
+            #[inline]
+            pub(crate) fn write_ptr<T: IBase + ?Sized>(&self, data: &mut Data, arg: &$ptr<T>) {
+                let typeid = arg.get_full_typeid();
+                data.write_bit7(typeid);
+                if typeid == 0 {
+                    return;
+                }
+
+                let addr = std::ops::Deref::deref(arg) as *const T as *const u8 as usize;
+                if let Some(&offset) = data.w_ptr_dict.get(&addr) {
+                    data.write_bit7(offset);
+                } else {
+                    data.w_count += 1;
+                    let offset = data.w_count;
+                    data.w_ptr_dict.insert(addr, offset);
+                    if let Some(ref mut keep) = data.tables.w_keep {
+                        keep.push_back((addr, Box::new(arg.clone())));
+                    }
+                    data.write_bit7(offset);
+                    arg.write(data, self);
+                }
+            }
+
+            /// 写入一个可空对象
+            #[inline]
+            fn write_opt_ptr<T: IBase + ?Sized>(&self, data: &mut Data, arg: &Option<$ptr<T>>) {
+                match arg {
+                    Some(arg) => self.write_ptr(data, arg),
+                    None => data.write_to(0u8),
+                }
+            }
+
+            /// 写入一个weak 已释放时写入空
+            #[inline]
+            fn write_weak<T: IBase>(&self, data: &mut Data, arg: &Option<Weak<T>>) {
+                self.write_opt_ptr(data, &arg.as_ref().and_then(Weak::upgrade));
+            }
+
+            /// 写入一个可空类型
+            #[inline]
+            fn write_option<T: WriteObject>(&self, data: &mut Data, arg: &Option<T>) {
+                match arg {
+                    Some(x) => {
+                        data.write_to(1u8);
+                        x.write_(data, self);
+                    }
+                    None => {
+                        data.write_to(0u8);
+                    }
+                }
+            }
+
+            /// 写入集合 长度+每个元素
+            #[inline]
+            fn write_items<I: Iterator, F: FnMut(&mut Data, I::Item)>(&self, data: &mut Data, len: usize, items: I, mut write: F) {
+                data.write_bit7(len as u64);
+                for item in items {
+                    write(data, item);
+                }
+            }
+
+            /// 读取对象头 返回 (type_id,offs) type_id 为0表示None
+            #[inline]
+            fn read_ptr_head(data: &mut Data) -> Result<(u32, u32), u32> {
+                let type_id = match data.read_bit7_u32() {
+                    None => return Err(line!()),
+                    Some((_, type_id)) => type_id,
+                };
+                if type_id == 0 {
+                    return Ok((0, 0));
+                }
+                match data.read_bit7_u32() {
+                    None => Err(line!()),
+                    Some((_, offs)) => Ok((type_id, offs)),
+                }
+            }
+
+            /// 读取一个 IBase 对象 保留实际类型
+            #[inline]
+            pub(crate) fn read_ptr_ibase(&self, data: &mut Data, v: &mut Option<$ptr<dyn IBase>>) -> Result<(), u32> {
+                let (type_id, offs) = Self::read_ptr_head(data)?;
+                if type_id == 0 {
+                    *v = None;
+                    return Ok(());
+                }
+
+                if offs == data.r_count + 1 {
+                    if let Some(alias) = self.aliases.get(&type_id) {
+                        *v = Some(self.read_alias(data, alias, offs)?);
+                        Ok(())
+                    } else if let Some(v) = v.as_ref().filter(|v| data.read_mode == $crate::ReadMode::Merge && v.get_full_typeid() == type_id) {
+                        data.r_count = offs;
+                        data.tables.r_ptr_dict.insert(offs, v.clone());
+                        v.read(data, self)
+                    } else if let Some(vv) = self.create_in(type_id, data) {
+                        data.r_count = offs;
+                        data.tables.r_ptr_dict.insert(offs, vv.clone());
+                        vv.read(data, self)?;
+                        *v = Some(vv);
+                        Ok(())
+                    } else {
+                        Err(line!())
+                    }
+                } else if let Some(o) = data.tables.r_ptr_dict.get(&offs) {
+                    *v = Some(o.clone());
+                    Ok(())
+                } else {
+                    Err(line!())
+                }
+            }
+
+            /// 按旧结构读取别名对象 并迁移为新类型
+            #[inline]
+            fn read_alias(&self, data: &mut Data, alias: &Alias, offs: u32) -> Result<$ptr<dyn IBase>, u32> {
+                let old = match (alias.create)() {
+                    Some(old) => old,
+                    None => return Err(line!()),
+                };
+                data.r_count = offs;
+                data.tables.r_ptr_dict.insert(offs, old.clone());
+                old.read(data, self)?;
+                match (alias.migrate)(old) {
+                    Some(new) if new.get_full_typeid() == alias.typeid => {
+                        data.tables.r_ptr_dict.insert(offs, new.clone());
+                        Ok(new)
+                    }
+                    _ => Err(line!()),
+                }
+            }
+
+            /// 读取一个 T 对象 可以读取到 T 的别名和通过指针转换注册的子类型
+            #[inline]
+            pub(crate) fn read_ptr<T: IObjectBase + 'static>(&self, data: &mut Data, v: &mut Option<$ptr<T>>) -> Result<(), u32> {
+                let (type_id, offs) = Self::read_ptr_head(data)?;
+                if type_id == 0 {
+                    *v = None;
+                    return Ok(());
+                }
+
+                if offs == data.r_count + 1 {
+                    if let Some(alias) = self.aliases.get(&type_id) {
+                        if alias.typeid != T::get_static_full_typeid() {
+                            return Err(line!());
+                        }
+                        match self.read_alias(data, alias, offs)?.cast::<T>() {
+                            Ok(vv) => {
+                                *v = Some(vv);
+                                Ok(())
+                            }
+                            Err(_) => Err(line!()),
+                        }
+                    } else if T::get_static_full_typeid() != type_id {
+                        // 子类型按实际类型读取 再转换为 T
+                        if !self.is_kind_of(type_id, T::get_static_full_typeid()) {
+                            return Err(line!());
+                        }
+                        let vv = match self.create_in(type_id, data) {
+                            Some(vv) => vv,
+                            None => return Err(line!()),
+                        };
+                        data.r_count = offs;
+                        data.tables.r_ptr_dict.insert(offs, vv.clone());
+                        vv.read(data, self)?;
+                        match self.upcast_to::<T>(vv) {
+                            Some(vv) => {
+                                *v = Some(vv);
+                                Ok(())
+                            }
+                            None => Err(line!()),
+                        }
+                    } else if let Some(v) = v.as_ref().filter(|_| data.read_mode == $crate::ReadMode::Merge) {
+                        // TYPEID 与 T 相同 已有对象的类型就是 T 可以原地读取
+                        data.r_count = offs;
+                        data.tables.r_ptr_dict.insert(offs, v.clone());
+                        v.read(data, self)
+                    } else if let Some(Ok(vv)) = self.create_in(type_id, data).map(|p| p.cast::<T>()) {
+                        data.r_count = offs;
+                        data.tables.r_ptr_dict.insert(offs, vv.clone());
+                        vv.read(data, self)?;
+                        *v = Some(vv);
+                        Ok(())
+                    } else {
+                        Err(line!())
+                    }
+                } else if let Some(o) = data.tables.r_ptr_dict.get(&offs) {
+                    match self.upcast_to::<T>(o.clone()) {
+                        Some(vv) => {
+                            *v = Some(vv);
+                            Ok(())
+                        }
+                        None => Err(line!()),
+                    }
+                } else {
+                    Err(line!())
+                }
+            }
+
+            /// 读取weak 目标对象在读取结束前由引用表持有
+            /// 读取结束后没有其他强引用时失效 需要保留时使用 read_graph
+            #[inline]
+            fn read_weak<T: IObjectBase + 'static>(&self, data: &mut Data, v: &mut Option<Weak<T>>) -> Result<(), u32> {
+                let mut p: Option<$ptr<T>> = None;
+                self.read_ptr(data, &mut p)?;
+                *v = p.map(|p| $ptr::downgrade(&p));
+                Ok(())
+            }
+
+            /// 读取一个option
+            #[inline]
+            fn read_option<T: ReadObject + Default>(&self, data: &mut Data, v: &mut Option<T>) -> Result<(), u32> {
+                if data.remaining() == 0 {
+                    return Err(line!());
+                }
+                if data.get_u8() == 1 {
+                    if let Some(v) = v.as_mut().filter(|_| data.read_mode == $crate::ReadMode::Merge) {
+                        v.read_(data, self)
+                    } else {
+                        *v = Some(self.read_value(data)?);
+                        Ok(())
+                    }
+                } else {
+                    *v = None;
+                    Ok(())
+                }
+            }
+
+            /// 读取一个新创建的值
+            #[inline]
+            fn read_value<T: ReadObject + Default>(&self, data: &mut Data) -> Result<T, u32> {
+                let mut v = T::default();
+                v.read_(data, self)?;
+                Ok(v)
+            }
+
+            /// 读取一个不为空的对象
+            #[inline]
+            fn read_some_ptr<T: IObjectBase + 'static>(&self, data: &mut Data) -> Result<$ptr<T>, u32> {
+                let mut v: Option<$ptr<T>> = None;
+                self.read_ptr(data, &mut v)?;
+                v.ok_or(line!())
+            }
+
+            /// 读取集合 长度+每个元素 全部读取成功后才返回
+            #[inline]
+            fn read_items<I, F: FnMut(&mut Data) -> Result<I, u32>>(&self, data: &mut Data, mut read: F) -> Result<Vec<I>, u32> {
+                let size = match data.read_bit7_u64() {
+                    None => return Err(line!()),
+                    Some((_, size)) => size,
+                };
+                let mut items = Vec::new();
+                for _ in 0..size {
+                    items.push(read(data)?);
+                }
+                Ok(items)
+            }
+        }
+
+        pub trait WriteObject {
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager);
+        }
+
+        impl<T: IBase + ?Sized> WriteObject for Option<$ptr<T>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_opt_ptr(data, self);
+            }
+        }
+
+        impl<T: IBase + WriteObject> WriteObject for Option<Weak<T>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_weak(data, self);
+            }
+        }
+
+        impl<T: IBase> WriteObject for T {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                self.write(data, obj_manager);
+            }
+        }
+
+        impl<T: WriteObject> WriteObject for Option<T> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_option(data, self);
+            }
+        }
+
+        impl<T: WriteObject> WriteObject for Vec<T> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, v| v.write_(data, obj_manager));
+            }
+        }
+
+        impl<T: IBase> WriteObject for Vec<$ptr<T>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, v| obj_manager.write_ptr(data, v));
+            }
+        }
+
+        impl WriteObject for String {
+            #[inline]
+            fn write_(&self, data: &mut Data, _: &ObjectManager) {
+                data.write_str_bit7(self);
+            }
+        }
+
+        impl WriteObject for Bytes {
+            #[inline]
+            fn write_(&self, data: &mut Data, _: &ObjectManager) {
+                data.write_buff_bit7(self);
+            }
+        }
+
+        impl<K: WriteObject, V: WriteObject> WriteObject for BTreeMap<K, V> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    k.write_(data, obj_manager);
+                    v.write_(data, obj_manager);
+                });
+            }
+        }
+
+        impl<K: WriteObject, V: IBase> WriteObject for BTreeMap<K, $ptr<V>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    k.write_(data, obj_manager);
+                    obj_manager.write_ptr(data, v);
+                });
+            }
+        }
+
+        impl<K: WriteObject, V: WriteObject> WriteObject for HashMap<K, V> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    k.write_(data, obj_manager);
+                    v.write_(data, obj_manager);
+                });
+            }
+        }
+
+        impl<K: WriteObject, V: IBase> WriteObject for HashMap<K, $ptr<V>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    k.write_(data, obj_manager);
+                    obj_manager.write_ptr(data, v);
+                });
+            }
+        }
+
+        impl<K: IBase, V: WriteObject> WriteObject for HashMap<$ptr<K>, V> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    obj_manager.write_ptr(data, k);
+                    v.write_(data, obj_manager);
+                });
+            }
+        }
+
+        impl<K: IBase, V: IBase> WriteObject for HashMap<$ptr<K>, $ptr<V>> {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                obj_manager.write_items(data, self.len(), self.iter(), |data, (k, v)| {
+                    obj_manager.write_ptr(data, k);
+                    obj_manager.write_ptr(data, v);
+                });
+            }
+        }
+
+        macro_rules! impl_integer_var {
+            ($type:ty) => {
+                impl WriteObject for $type {
+                    #[inline]
+                    fn write_(&self, data: &mut Data, _: &ObjectManager) {
+                        data.write_bit7(*self)
+                    }
+                }
+            };
+        }
+
+        impl_integer_var!(i16);
+        impl_integer_var!(u16);
+        impl_integer_var!(i32);
+        impl_integer_var!(u32);
+        impl_integer_var!(i64);
+        impl_integer_var!(u64);
+
+        macro_rules! impl_write_object_fixed {
+            ($type:ty, $put:ident) => {
+                impl WriteObject for $type {
+                    #[inline]
+                    fn write_(&self, data: &mut Data, _: &ObjectManager) {
+                        data.$put(*self);
+                    }
+                }
+            };
+        }
+
+        impl_write_object_fixed!(i8, put_i8);
+        impl_write_object_fixed!(u8, put_u8);
+        impl_write_object_fixed!(i128, put_i128_le);
+        impl_write_object_fixed!(u128, put_u128_le);
+        impl_write_object_fixed!(f32, put_f32_le);
+        impl_write_object_fixed!(f64, put_f64_le);
+
+        impl WriteObject for bool {
+            #[inline]
+            fn write_(&self, data: &mut Data, _: &ObjectManager) {
+                data.put_u8(*self as u8);
+            }
+        }
+
+        pub trait ReadObject {
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32>;
+        }
+
+        pub trait ReadOnlyObject {
+            fn read_(&self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32>;
+        }
+
+        impl<T: IBase + 'static> ReadObject for T {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                self.read(data, obj_manager)
+            }
+        }
+
+        impl ReadObject for Option<$ptr<dyn IBase>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                obj_manager.read_ptr_ibase(data, self)
+            }
+        }
+
+        impl<T: IObjectBase + 'static> ReadObject for Option<$ptr<T>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                obj_manager.read_ptr(data, self)
+            }
+        }
+
+        impl<T: IObjectBase + 'static> ReadObject for Option<Weak<T>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                obj_manager.read_weak(data, self)
+            }
+        }
+
+        impl<T: ReadObject + Default> ReadObject for Option<T> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                obj_manager.read_option(data, self)
+            }
+        }
+
+        impl<T: ReadObject + Default> ReadObject for Vec<T> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| obj_manager.read_value(data))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<T: IObjectBase + 'static> ReadObject for Vec<$ptr<T>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| obj_manager.read_some_ptr(data))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<T: IObjectBase + 'static> ReadObject for Vec<Weak<T>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok($ptr::downgrade(&obj_manager.read_some_ptr::<T>(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: ReadObject + Default + Ord, V: ReadObject + Default> ReadObject for BTreeMap<K, V> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_value(data)?, obj_manager.read_value(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: ReadObject + Default + Ord, V: IObjectBase + 'static> ReadObject for BTreeMap<K, $ptr<V>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_value(data)?, obj_manager.read_some_ptr(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: ReadObject + Default + Eq + Hash, V: ReadObject + Default> ReadObject for HashMap<K, V> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_value(data)?, obj_manager.read_value(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: ReadObject + Default + Eq + Hash, V: IObjectBase + 'static> ReadObject for HashMap<K, $ptr<V>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_value(data)?, obj_manager.read_some_ptr(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: IObjectBase + Eq + Hash + 'static, V: ReadObject + Default> ReadObject for HashMap<$ptr<K>, V> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_some_ptr(data)?, obj_manager.read_value(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl<K: IObjectBase + Eq + Hash + 'static, V: IObjectBase + 'static> ReadObject for HashMap<$ptr<K>, $ptr<V>> {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let items = obj_manager.read_items(data, |data| Ok((obj_manager.read_some_ptr(data)?, obj_manager.read_some_ptr(data)?)))?;
+                data.merge_items(self, items);
+                Ok(())
+            }
+        }
+
+        impl ReadObject for String {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+                match data.get_str_bit7() {
+                    None => return Err(line!()),
+                    Some(p) => *self = p,
+                }
+                Ok(())
+            }
+        }
+
+        impl ReadObject for Bytes {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+                match data.get_bytes_bit7() {
+                    None => return Err(line!()),
+                    Some(p) => *self = p,
+                }
+                Ok(())
+            }
+        }
+
+        macro_rules! impl_read_object_integer {
+            ($type:ty) => {
+                impl ReadObject for $type {
+                    #[inline]
+                    fn read_(&mut self, data: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+                        match data.get_bit7::<$type>() {
+                            None => Err(line!()),
+                            Some((_, p)) => {
+                                *self = p;
+                                Ok(())
+                            }
+                        }
+                    }
+                }
+            };
+        }
+
+        impl_read_object_integer!(i16);
+        impl_read_object_integer!(u16);
+        impl_read_object_integer!(i32);
+        impl_read_object_integer!(u32);
+        impl_read_object_integer!(i64);
+        impl_read_object_integer!(u64);
+
+        macro_rules! impl_read_object_fixed {
+            ($type:ty, $get:ident) => {
+                impl ReadObject for $type {
+                    #[inline]
+                    fn read_(&mut self, data: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+                        if data.remaining() < std::mem::size_of::<$type>() {
+                            return Err(line!());
+                        }
+                        *self = data.$get();
+                        Ok(())
+                    }
+                }
+            };
+        }
+
+        impl_read_object_fixed!(i8, get_i8);
+        impl_read_object_fixed!(u8, get_u8);
+        impl_read_object_fixed!(i128, get_i128_le);
+        impl_read_object_fixed!(u128, get_u128_le);
+        impl_read_object_fixed!(f32, get_f32_le);
+        impl_read_object_fixed!(f64, get_f64_le);
+
+        impl ReadObject for bool {
+            #[inline]
+            fn read_(&mut self, data: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+                if data.remaining() == 0 {
+                    return Err(line!());
+                }
+                match data.get_u8() {
+                    0 => *self = false,
+                    1 => *self = true,
+                    _ => return Err(line!()),
+                }
+                Ok(())
+            }
+        }
+    };
+}
@@ -0,0 +1,351 @@
+use crate::interface::{IBase, IBaseAsRc, IObjectBase};
+use bytes::Bytes;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+/// 字段信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub type_name: &'static str,
+}
+
+/// 反射读取写入的值
+#[derive(Debug, Clone)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bytes(Bytes),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// 按值嵌入的结构 (字段名,值)
+    Struct(Vec<(String, Value)>),
+    Object(Rc<dyn IBase>),
+}
+
+impl PartialEq for Value {
+    /// Object 按指向比较
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::None, Value::None) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::UInt(a), Value::UInt(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::Struct(a), Value::Struct(b)) => a == b,
+            (Value::Object(a), Value::Object(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// 反射接口 通过字段名读取写入字段
+/// 实现时同时重载 IBase::as_reflect 返回 Some(self)
+pub trait Reflect: IBase {
+    fn fields(&self) -> &[FieldInfo];
+    fn get_field(&self, name: &str) -> Option<Value>;
+    fn set_field(&self, name: &str, value: Value) -> Result<(), String>;
+}
+
+/// 值与 Value 的转换
+pub trait ReflectValue: Sized {
+    fn to_value(&self) -> Value;
+    fn from_value(value: Value) -> Result<Self, String>;
+}
+
+/// 结构字段的读取写入 通过 Cell/RefCell 写入
+pub trait ReflectField {
+    fn get_value(&self) -> Value;
+    fn set_value(&self, value: Value) -> Result<(), String>;
+}
+
+/// 类型不匹配错误
+#[inline]
+fn mismatch<T>(value: &Value) -> String {
+    format!("expected {}, got {:?}", std::any::type_name::<T>(), value)
+}
+
+impl<T: ReflectValue + Copy> ReflectField for Cell<T> {
+    #[inline]
+    fn get_value(&self) -> Value {
+        self.get().to_value()
+    }
+
+    #[inline]
+    fn set_value(&self, value: Value) -> Result<(), String> {
+        self.set(T::from_value(value)?);
+        Ok(())
+    }
+}
+
+impl<T: ReflectValue> ReflectField for RefCell<T> {
+    #[inline]
+    fn get_value(&self) -> Value {
+        self.borrow().to_value()
+    }
+
+    #[inline]
+    fn set_value(&self, value: Value) -> Result<(), String> {
+        let v = T::from_value(value)?;
+        *self.borrow_mut() = v;
+        Ok(())
+    }
+}
+
+macro_rules! impl_reflect_int {
+    ($variant:ident, $base:ty, $($type:ty),+) => {
+        $(
+            impl ReflectValue for $type {
+                #[inline]
+                fn to_value(&self) -> Value {
+                    Value::$variant(*self as $base)
+                }
+
+                #[inline]
+                fn from_value(value: Value) -> Result<Self, String> {
+                    let v = match value {
+                        Value::Int(v) => <$type>::try_from(v).ok(),
+                        Value::UInt(v) => <$type>::try_from(v).ok(),
+                        _ => None,
+                    };
+                    v.ok_or_else(|| mismatch::<$type>(&value))
+                }
+            }
+        )+
+    };
+}
+
+impl_reflect_int!(Int, i64, i8, i16, i32, i64);
+impl_reflect_int!(UInt, u64, u8, u16, u32, u64);
+
+macro_rules! impl_reflect_value {
+    ($variant:ident, $type:ty, $from:expr) => {
+        impl ReflectValue for $type {
+            #[inline]
+            fn to_value(&self) -> Value {
+                Value::$variant($from(self))
+            }
+
+            #[inline]
+            fn from_value(value: Value) -> Result<Self, String> {
+                match value {
+                    Value::$variant(v) => Ok(v as $type),
+                    _ => Err(mismatch::<$type>(&value)),
+                }
+            }
+        }
+    };
+}
+
+impl_reflect_value!(Bool, bool, |v: &bool| *v);
+impl_reflect_value!(Float, f64, |v: &f64| *v);
+impl_reflect_value!(Str, String, |v: &String| v.clone());
+impl_reflect_value!(Bytes, Bytes, |v: &Bytes| v.clone());
+
+impl ReflectValue for f32 {
+    #[inline]
+    fn to_value(&self) -> Value {
+        Value::Float(*self as f64)
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Float(v) => Ok(v as f32),
+            _ => Err(mismatch::<f32>(&value)),
+        }
+    }
+}
+
+/// 按值嵌入的结构 转换为 Value::Struct
+/// 只修改部分字段时先 get_field 取得完整的 Value::Struct 修改后再写入
+impl<T: Reflect + Default> ReflectValue for T {
+    #[inline]
+    fn to_value(&self) -> Value {
+        let mut fields = Vec::new();
+        for info in self.fields() {
+            if let Some(v) = self.get_field(info.name) {
+                fields.push((info.name.to_string(), v));
+            }
+        }
+        Value::Struct(fields)
+    }
+
+    /// 需要包含所有字段 缺少字段时返回错误 不使用默认值补全
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Struct(fields) => {
+                let v = T::default();
+                for info in v.fields() {
+                    if !fields.iter().any(|(name, _)| name == info.name) {
+                        return Err(format!("missing field {} of {}", info.name, std::any::type_name::<T>()));
+                    }
+                }
+                for (name, field) in fields {
+                    v.set_field(&name, field)?;
+                }
+                Ok(v)
+            }
+            _ => Err(mismatch::<T>(&value)),
+        }
+    }
+}
+
+impl ReflectValue for Rc<dyn IBase> {
+    #[inline]
+    fn to_value(&self) -> Value {
+        Value::Object(self.clone())
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Object(v) => Ok(v),
+            _ => Err(mismatch::<Self>(&value)),
+        }
+    }
+}
+
+impl<T: IObjectBase> ReflectValue for Rc<T> {
+    #[inline]
+    fn to_value(&self) -> Value {
+        Value::Object(self.clone())
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::Object(v) => v.cast::<T>().map_err(|v| format!("expected {}, got typeid {}", std::any::type_name::<T>(), v.get_full_typeid())),
+            _ => Err(mismatch::<Self>(&value)),
+        }
+    }
+}
+
+/// 已释放的 Weak 转换为 Value::None
+impl<T: IObjectBase> ReflectValue for Weak<T> {
+    #[inline]
+    fn to_value(&self) -> Value {
+        match self.upgrade() {
+            Some(v) => Value::Object(v),
+            None => Value::None,
+        }
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::None => Ok(Weak::new()),
+            value => Ok(Rc::downgrade(&Rc::<T>::from_value(value)?)),
+        }
+    }
+}
+
+impl<T: ReflectValue> ReflectValue for Option<T> {
+    #[inline]
+    fn to_value(&self) -> Value {
+        match self {
+            Some(v) => v.to_value(),
+            None => Value::None,
+        }
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::None => Ok(None),
+            value => Ok(Some(T::from_value(value)?)),
+        }
+    }
+}
+
+impl<T: ReflectValue> ReflectValue for Vec<T> {
+    #[inline]
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(|v| v.to_value()).collect())
+    }
+
+    #[inline]
+    fn from_value(value: Value) -> Result<Self, String> {
+        match value {
+            Value::List(list) => list.into_iter().map(T::from_value).collect(),
+            _ => Err(mismatch::<Self>(&value)),
+        }
+    }
+}
+
+macro_rules! impl_reflect_map {
+    ($map:ident, $($bound:path),+) => {
+        impl<K: ReflectValue $(+ $bound)+, V: ReflectValue> ReflectValue for $map<K, V> {
+            #[inline]
+            fn to_value(&self) -> Value {
+                Value::Map(self.iter().map(|(k, v)| (k.to_value(), v.to_value())).collect())
+            }
+
+            #[inline]
+            fn from_value(value: Value) -> Result<Self, String> {
+                match value {
+                    Value::Map(map) => map.into_iter().map(|(k, v)| Ok((K::from_value(k)?, V::from_value(v)?))).collect(),
+                    _ => Err(mismatch::<Self>(&value)),
+                }
+            }
+        }
+    };
+}
+
+impl_reflect_map!(HashMap, Eq, Hash);
+impl_reflect_map!(BTreeMap, Ord);
+
+/// 字段的类型名 用于 impl_reflect! 生成 FieldInfo
+#[doc(hidden)]
+#[inline]
+pub fn type_name_of<T>(_: &T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+/// 按字段顺序生成 Reflect 字段需要实现 ReflectField 类型名为 std::any::type_name
+/// impl_reflect!(Type { a, b }) 同时在 impl IBase 中重载 as_reflect 返回 Some(self)
+#[macro_export]
+macro_rules! impl_reflect {
+    ($($ty:ident { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::reflect::Reflect for $ty {
+                #[inline]
+                fn fields(&self) -> &[$crate::reflect::FieldInfo] {
+                    static FIELDS: std::sync::OnceLock<Vec<$crate::reflect::FieldInfo>> = std::sync::OnceLock::new();
+                    FIELDS.get_or_init(|| {
+                        vec![$($crate::reflect::FieldInfo { name: stringify!($field), type_name: $crate::reflect::type_name_of(&self.$field) }),*]
+                    })
+                }
+
+                #[inline]
+                fn get_field(&self, name: &str) -> Option<$crate::reflect::Value> {
+                    match name {
+                        $(stringify!($field) => Some($crate::reflect::ReflectField::get_value(&self.$field)),)*
+                        _ => None,
+                    }
+                }
+
+                #[inline]
+                fn set_field(&self, name: &str, value: $crate::reflect::Value) -> Result<(), String> {
+                    match name {
+                        $(stringify!($field) => $crate::reflect::ReflectField::set_value(&self.$field, value),)*
+                        _ => Err(format!("no field {}", name)),
+                    }
+                }
+            }
+        )+
+    };
+}
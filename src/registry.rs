@@ -0,0 +1,45 @@
+use crate::interface::IObjectBase;
+use crate::object_manager::ObjectManager;
+
+/// 自动注册信息 由 register_type! 在启动时提交
+pub struct TypeRegistration {
+    pub typeid: u32,
+    pub name: &'static str,
+    pub module: &'static str,
+    pub register: fn(&mut ObjectManager),
+}
+
+impl TypeRegistration {
+    #[inline]
+    pub fn new<T: IObjectBase + 'static>(module: &'static str) -> TypeRegistration {
+        TypeRegistration {
+            typeid: T::get_static_full_typeid(),
+            name: std::any::type_name::<T>(),
+            module,
+            register: ObjectManager::register::<T>,
+        }
+    }
+}
+
+inventory::collect!(TypeRegistration);
+
+/// 所有通过 register_type! 提交的类型
+#[inline]
+pub fn registrations() -> impl Iterator<Item = &'static TypeRegistration> {
+    inventory::iter::<TypeRegistration>.into_iter()
+}
+
+/// 提交类型到全局注册表 ObjectManager::with_all_registered 时自动注册
+/// #[derive(build)] 为结构生成 IObjectBase 后 应在同一模块展开 register_type!(Self类型)
+/// 手写 IObjectBase 的结构在实现后调用一次
+#[macro_export]
+macro_rules! register_type {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            $crate::inventory::submit! {
+                #![crate = $crate]
+                $crate::registry::TypeRegistration::new::<$ty>(module_path!())
+            }
+        )+
+    };
+}
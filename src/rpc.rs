@@ -0,0 +1,294 @@
+use crate::codec::{read_frame, write_frame};
+use crate::dispatcher::Dispatcher;
+use crate::interface::{namespace_of, IBase, IObjectBase};
+use crate::object_manager::{read_error, IObjectManager, ObjectManager};
+use crate::Data;
+use ahash::AHashMap;
+use bytes::{Buf, BufMut};
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::io;
+use std::rc::Rc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::oneshot;
+
+const KIND_REQUEST: u8 = 0;
+const KIND_RESPONSE: u8 = 1;
+const KIND_ERROR: u8 = 2;
+
+/// RpcError 使用的命名空间 不与用户类型冲突
+pub const RPC_NAMESPACE: u16 = namespace_of("pkg.rpc");
+
+/// 无法解析请求帧时 错误帧使用的call_id 请求不会使用0
+pub const NO_CALL_ID: u32 = 0;
+
+/// RPC 远程错误 TYPEID 为 RPC_NAMESPACE 下的 u16::MAX
+#[derive(Debug, Default, PartialEq)]
+pub struct RpcError {
+    pub code: Cell<i32>,
+    pub message: RefCell<String>,
+}
+
+impl RpcError {
+    /// 服务端没有注册该请求类型
+    pub const NOT_FOUND: i32 = -1;
+    /// 连接关闭 请求被取消
+    pub const CANCELED: i32 = -2;
+    /// 帧格式错误
+    pub const BAD_FRAME: i32 = -3;
+
+    #[inline]
+    pub fn new(code: i32, message: &str) -> RpcError {
+        RpcError {
+            code: Cell::new(code),
+            message: RefCell::new(message.to_string()),
+        }
+    }
+}
+
+impl IBase for RpcError {
+    fn write(&self, data: &mut Data, o: &ObjectManager) {
+        o.write(data, &self.code);
+        o.write(data, &self.message);
+    }
+
+    fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        o.read(data, &self.code)?;
+        o.read(data, &self.message)?;
+        Ok(())
+    }
+
+    fn get_typeid(&self) -> u16 {
+        Self::get_static_typeid()
+    }
+
+    fn get_namespace(&self) -> u16 {
+        Self::get_static_namespace()
+    }
+}
+
+impl IObjectBase for RpcError {
+    fn get_static_typeid() -> u16 {
+        u16::MAX
+    }
+
+    fn get_static_namespace() -> u16 {
+        RPC_NAMESPACE
+    }
+
+    fn new() -> Option<Rc<dyn IBase>> {
+        Some(Rc::new(Self::default()))
+    }
+}
+
+/// RPC 调用结果
+pub type RpcResult = Result<Rc<dyn IBase>, RpcError>;
+
+/// 生成一帧 bit7 call_id + kind + body
+#[inline]
+fn make_frame<T: IBase + ?Sized>(obj_manager: &ObjectManager, call_id: u32, kind: u8, arg: &Rc<T>) -> Data {
+    let mut data = Data::new();
+    data.write_bit7(call_id);
+    data.put_u8(kind);
+    obj_manager.write_core(&mut data, arg);
+    data
+}
+
+/// 生成一个错误帧
+#[inline]
+fn make_error_frame(obj_manager: &ObjectManager, call_id: u32, err: &RpcError) -> Data {
+    let mut data = Data::new();
+    data.write_bit7(call_id);
+    data.put_u8(KIND_ERROR);
+    obj_manager.write_to(&mut data, err);
+    data
+}
+
+/// 读取帧头 返回 (call_id,kind)
+#[inline]
+fn read_head(data: &mut Data) -> Result<(u32, u8), u32> {
+    let call_id = match data.read_bit7_u32() {
+        None => return Err(line!()),
+        Some((_, call_id)) => call_id,
+    };
+    if data.remaining() == 0 {
+        return Err(line!());
+    }
+    Ok((call_id, data.get_u8()))
+}
+
+type Pending = Box<dyn FnOnce(RpcResult)>;
+
+/// RPC 客户端 为每个请求分配call_id 并匹配响应
+pub struct RpcClient {
+    obj_manager: Rc<ObjectManager>,
+    id: Cell<u32>,
+    pending: RefCell<AHashMap<u32, Pending>>,
+}
+
+impl RpcClient {
+    #[inline]
+    pub fn new(obj_manager: Rc<ObjectManager>) -> RpcClient {
+        RpcClient {
+            obj_manager,
+            id: Cell::new(0),
+            pending: RefCell::new(AHashMap::new()),
+        }
+    }
+
+    /// 生成请求帧 响应到达时调用回调
+    #[inline]
+    pub fn call_with<T: IBase + ?Sized, F: FnOnce(RpcResult) + 'static>(&self, arg: &Rc<T>, f: F) -> Data {
+        let call_id = match self.id.get().wrapping_add(1) {
+            NO_CALL_ID => NO_CALL_ID + 1,
+            call_id => call_id,
+        };
+        self.id.set(call_id);
+        self.pending.borrow_mut().insert(call_id, Box::new(f));
+        make_frame(&self.obj_manager, call_id, KIND_REQUEST, arg)
+    }
+
+    /// 生成请求帧 返回等待响应的future
+    #[inline]
+    pub fn call<T: IBase + ?Sized>(&self, arg: &Rc<T>) -> (Data, impl Future<Output = RpcResult>) {
+        let (tx, rx) = oneshot::channel();
+        let frame = self.call_with(arg, move |ret| {
+            let _ = tx.send(ret);
+        });
+        (frame, async move {
+            match rx.await {
+                Ok(ret) => ret,
+                Err(_) => Err(RpcError::new(RpcError::CANCELED, "canceled")),
+            }
+        })
+    }
+
+    /// 处理一个响应帧 读取到call_id 后即移除等待的请求
+    /// 响应无法解析时 请求以 BAD_FRAME 错误完成 并返回错误
+    #[inline]
+    pub fn on_response(&self, frame: &[u8]) -> Result<(), u32> {
+        let mut data = Data::with_capacity(frame.len());
+        data.write(frame);
+        let (call_id, kind) = read_head(&mut data)?;
+        let f = match self.pending.borrow_mut().remove(&call_id) {
+            Some(f) => f,
+            None => return Err(line!()),
+        };
+        let ret = match kind {
+            KIND_RESPONSE => self.obj_manager.read_core(&mut data).map(Ok),
+            KIND_ERROR => {
+                let mut err = RpcError::default();
+                self.obj_manager.read_from(&mut data, &mut err).map(|_| Err(err))
+            }
+            _ => Err(line!()),
+        };
+
+        match ret {
+            Ok(ret) => {
+                f(ret);
+                Ok(())
+            }
+            Err(line) => {
+                f(Err(RpcError::new(RpcError::BAD_FRAME, &format!("read response error:{}", line))));
+                Err(line)
+            }
+        }
+    }
+
+    /// 从 AsyncRead 读取一个响应帧并处理
+    #[inline]
+    pub async fn recv<R: AsyncRead + Unpin>(&self, reader: &mut R) -> io::Result<()> {
+        let frame = read_frame(reader).await?;
+        self.on_response(&frame).map_err(read_error)
+    }
+
+    /// 等待响应的请求数量
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.borrow().len()
+    }
+
+    /// 取消所有等待的请求
+    #[inline]
+    pub fn cancel_all(&self) {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        for (_, f) in pending {
+            f(Err(RpcError::new(RpcError::CANCELED, "canceled")));
+        }
+    }
+}
+
+/// RPC 服务端 根据请求TYPEID 调用注册的服务
+pub struct RpcServer {
+    obj_manager: Rc<ObjectManager>,
+    dispatcher: Dispatcher<Option<RpcResult>>,
+}
+
+impl RpcServer {
+    #[inline]
+    pub fn new(obj_manager: Rc<ObjectManager>) -> RpcServer {
+        RpcServer {
+            obj_manager,
+            dispatcher: Dispatcher::new(),
+        }
+    }
+
+    /// 注册请求 T 的服务
+    #[inline]
+    pub fn on<T: IObjectBase + 'static, F: Fn(Rc<T>) -> RpcResult + 'static>(&mut self, f: F) -> &mut Self {
+        self.dispatcher.on::<T, _>(move |req, ret| *ret = Some(f(req)));
+        self
+    }
+
+    /// 处理一个请求帧 返回响应帧
+    #[inline]
+    pub fn handle(&self, frame: &[u8]) -> Result<Data, u32> {
+        let mut data = Data::with_capacity(frame.len());
+        data.write(frame);
+        let (call_id, kind) = read_head(&mut data)?;
+        if kind != KIND_REQUEST {
+            return Ok(make_error_frame(&self.obj_manager, call_id, &RpcError::new(RpcError::BAD_FRAME, "not a request")));
+        }
+
+        let req = match self.obj_manager.read_core(&mut data) {
+            Ok(req) => req,
+            Err(line) => {
+                let err = RpcError::new(RpcError::BAD_FRAME, &format!("read request error:{}", line));
+                return Ok(make_error_frame(&self.obj_manager, call_id, &err));
+            }
+        };
+
+        let typeid = req.get_full_typeid();
+        let mut ret = None;
+        self.dispatcher.dispatch(req, &mut ret);
+        match ret {
+            Some(Ok(resp)) => Ok(make_frame(&self.obj_manager, call_id, KIND_RESPONSE, &resp)),
+            Some(Err(err)) => Ok(make_error_frame(&self.obj_manager, call_id, &err)),
+            None => {
+                let err = RpcError::new(RpcError::NOT_FOUND, &format!("not found typeid:{}", typeid));
+                Ok(make_error_frame(&self.obj_manager, call_id, &err))
+            }
+        }
+    }
+
+    /// 循环读取请求并写回响应 直到连接关闭
+    /// 无法解析的帧回复 call_id 为 NO_CALL_ID 的 BAD_FRAME 错误 并继续处理后续请求
+    #[inline]
+    pub async fn serve<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(&self, reader: &mut R, writer: &mut W) -> io::Result<()> {
+        loop {
+            let frame = match read_frame(reader).await {
+                Ok(frame) => frame,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            let resp = match self.handle(&frame) {
+                Ok(resp) => resp,
+                Err(line) => {
+                    let err = RpcError::new(RpcError::BAD_FRAME, &format!("read frame error:{}", line));
+                    make_error_frame(&self.obj_manager, NO_CALL_ID, &err)
+                }
+            };
+            write_frame(writer, Buf::bytes(&resp)).await?;
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::data::{ModelData, RcTables, Tables};
+use crate::interface::IBase;
+use crate::object_manager::{ObjectManager, ReadObject, WriteObject};
+use crate::Data;
+use ahash::AHashMap;
+use std::collections::VecDeque;
+use std::mem::{swap, take};
+use std::rc::Rc;
+
+/// 会话引用表的默认容量
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+/// 编码会话的引用表 M 为对象模型的引用表类型 RC 和线程安全模型共用
+pub(crate) struct EncodeTable<M: Tables> {
+    w_ptr_dict: AHashMap<usize, u32>,
+    count: u32,
+    keep: VecDeque<(usize, M::Keep)>,
+    capacity: usize,
+}
+
+impl<M: Tables> EncodeTable<M> {
+    #[inline]
+    pub(crate) fn with_capacity(capacity: usize) -> EncodeTable<M> {
+        EncodeTable {
+            w_ptr_dict: AHashMap::new(),
+            count: 0,
+            keep: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// 使用引用表写入 write 写入一个消息
+    #[inline]
+    pub(crate) fn write<D: ModelData<Tables = M>, F: FnOnce(&mut D)>(&mut self, data: &mut D, write: F) {
+        let inner = data.data_mut();
+        swap(&mut inner.w_ptr_dict, &mut self.w_ptr_dict);
+        inner.w_count = self.count;
+        *inner.tables.w_keep() = Some(take(&mut self.keep));
+        write(data);
+        let inner = data.data_mut();
+        swap(&mut inner.w_ptr_dict, &mut self.w_ptr_dict);
+        inner.w_ptr_dict.clear();
+        self.count = inner.w_count;
+        inner.w_count = 0;
+        self.keep = inner.tables.w_keep().take().unwrap_or_default();
+        while self.keep.len() > self.capacity {
+            if let Some((addr, _)) = self.keep.pop_front() {
+                self.w_ptr_dict.remove(&addr);
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.keep.len()
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.w_ptr_dict.clear();
+        self.keep.clear();
+        self.count = 0;
+    }
+}
+
+/// 解码会话的引用表 M 为对象模型的引用表类型 RC 和线程安全模型共用
+pub(crate) struct DecodeTable<M: Tables> {
+    r_ptr_dict: AHashMap<u32, M::Ptr>,
+    count: u32,
+    evicted: u32,
+    capacity: usize,
+    poisoned: bool,
+}
+
+impl<M: Tables> DecodeTable<M> {
+    #[inline]
+    pub(crate) fn with_capacity(capacity: usize) -> DecodeTable<M> {
+        DecodeTable {
+            r_ptr_dict: AHashMap::new(),
+            count: 0,
+            evicted: 0,
+            capacity,
+            poisoned: false,
+        }
+    }
+
+    /// 使用引用表 read 读取一个消息到 arg
+    /// 失败时恢复读取位置 会话失效
+    #[inline]
+    pub(crate) fn read<D: ModelData<Tables = M>, T, F: FnOnce(&mut T, &mut D) -> Result<(), u32>>(
+        &mut self, data: &mut D, arg: &mut T, read: F,
+    ) -> Result<(), u32> {
+        if self.poisoned {
+            return Err(line!());
+        }
+        let inner = data.data_mut();
+        let offset = inner.get_position();
+        swap(inner.tables.r_ptr_dict(), &mut self.r_ptr_dict);
+        inner.r_count = self.count;
+        let ret = read(arg, data);
+        let inner = data.data_mut();
+        swap(inner.tables.r_ptr_dict(), &mut self.r_ptr_dict);
+        inner.tables.r_ptr_dict().clear();
+        self.count = inner.r_count;
+        inner.r_count = 0;
+        // 按编号从小到大淘汰
+        while self.r_ptr_dict.len() > self.capacity && self.evicted < self.count {
+            self.evicted += 1;
+            self.r_ptr_dict.remove(&self.evicted);
+        }
+        match ret {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                self.poisoned = true;
+                inner.set_position(offset);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.r_ptr_dict.len()
+    }
+
+    #[inline]
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.r_ptr_dict.clear();
+        self.count = 0;
+        self.evicted = 0;
+        self.poisoned = false;
+    }
+}
+
+/// 编码会话 引用表在同一连接的多个消息间保留 之后的消息可以引用之前发送过的对象
+/// 引用表超过容量时在消息结束后淘汰条目 DecodeSession 需要使用相同的容量
+/// 淘汰按首次写入的顺序(FIFO) 再次引用不会延后淘汰 淘汰后再写入时完整发送并分配新编号
+/// 表中的对象会被持有 直到被淘汰
+pub struct EncodeSession {
+    table: EncodeTable<RcTables>,
+}
+
+impl Default for EncodeSession {
+    #[inline]
+    fn default() -> Self {
+        EncodeSession::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl EncodeSession {
+    /// 容量为 DEFAULT_CAPACITY
+    #[inline]
+    pub fn new() -> EncodeSession {
+        EncodeSession::default()
+    }
+
+    /// 引用表最多保留 capacity 个对象
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> EncodeSession {
+        EncodeSession {
+            table: EncodeTable::with_capacity(capacity),
+        }
+    }
+
+    /// 写入一个消息
+    #[inline]
+    pub fn write_to<T: WriteObject>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &T) {
+        self.table.write(data, |data| arg.write_(data, obj_manager));
+    }
+
+    /// 写入一个 RC<IBASE> 消息
+    #[inline]
+    pub fn write_core<T: IBase + ?Sized>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &Rc<T>) {
+        self.table.write(data, |data| obj_manager.write_ptr(data, arg));
+    }
+
+    /// 引用表中的对象数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    /// 清空引用表 需要与 DecodeSession::clear 同时调用
+    #[inline]
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
+
+/// 解码会话 与 EncodeSession 对应 引用表在多个消息间保留
+/// 按编号从小到大淘汰 与 EncodeSession 的FIFO顺序一致
+/// 读取失败后编号与编码端不再一致 会话失效 之后的读取返回错误 需要与 EncodeSession 同时 clear
+pub struct DecodeSession {
+    table: DecodeTable<RcTables>,
+}
+
+impl Default for DecodeSession {
+    #[inline]
+    fn default() -> Self {
+        DecodeSession::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl DecodeSession {
+    /// 容量为 DEFAULT_CAPACITY
+    #[inline]
+    pub fn new() -> DecodeSession {
+        DecodeSession::default()
+    }
+
+    /// 引用表最多保留 capacity 个对象 需要与 EncodeSession 一致
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> DecodeSession {
+        DecodeSession {
+            table: DecodeTable::with_capacity(capacity),
+        }
+    }
+
+    /// 读取一个消息 失败时恢复读取位置 会话失效
+    /// 不是原子的 失败时 arg 中已读取的部分不恢复 需要原子读取时使用 read_from_atomic
+    #[inline]
+    pub fn read_from<T: ReadObject>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+        self.table.read(data, arg, |arg, data| arg.read_(data, obj_manager))
+    }
+
+    /// 读取一个消息到新创建的值 成功后替换 arg 失败时 arg 不变
+    #[inline]
+    pub fn read_from_atomic<T: ReadObject + Default>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+        let mut value = T::default();
+        self.read_from(obj_manager, data, &mut value)?;
+        *arg = value;
+        Ok(())
+    }
+
+    /// 读取一个 RC<IBASE> 消息
+    #[inline]
+    pub fn read_core(&mut self, obj_manager: &ObjectManager, data: &mut Data) -> Result<Rc<dyn IBase>, u32> {
+        let mut value: Option<Rc<dyn IBase>> = None;
+        self.read_from(obj_manager, data, &mut value)?;
+        match value {
+            None => Err(line!()),
+            Some(p) => Ok(p),
+        }
+    }
+
+    /// 引用表中的对象数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    /// 读取失败后会话失效 clear 后恢复
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.table.is_poisoned()
+    }
+
+    /// 清空引用表 需要与 EncodeSession::clear 同时调用
+    #[inline]
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
@@ -0,0 +1,78 @@
+use crate::codec::{read_frame, write_frame, PkgCodec};
+use crate::object_manager::read_error;
+use crate::sync::{IBase, IObjectManager, ObjectManager};
+use crate::sync::data::Data;
+use bytes::{Buf, BytesMut};
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// 线程安全对象编解码 解码出 ARC<IBASE>
+#[derive(Clone)]
+pub struct ObjectCodec {
+    obj_manager: Arc<ObjectManager>,
+    frame: PkgCodec,
+}
+
+impl ObjectCodec {
+    #[inline]
+    pub fn new(obj_manager: Arc<ObjectManager>) -> ObjectCodec {
+        ObjectCodec {
+            obj_manager,
+            frame: PkgCodec::new(),
+        }
+    }
+
+    /// 设置帧长度上限
+    #[inline]
+    pub fn set_max_frame_length(&mut self, max_frame_length: usize) {
+        self.frame.set_max_frame_length(max_frame_length);
+    }
+}
+
+impl Decoder for ObjectCodec {
+    type Item = Arc<dyn IBase>;
+    type Error = io::Error;
+
+    #[inline]
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.frame.decode(src)? {
+            None => Ok(None),
+            Some(frame) => {
+                let mut data = Data::from_bytes(frame.freeze());
+                self.obj_manager.read_core(&mut data).map(Some).map_err(read_error)
+            }
+        }
+    }
+}
+
+impl<T: IBase + ?Sized> Encoder<Arc<T>> for ObjectCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: Arc<T>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut data = Data::new();
+        self.obj_manager.write_core(&mut data, &item);
+        self.frame.encode(Buf::bytes(&*data), dst)
+    }
+}
+
+/// 从 AsyncRead 读取一个对象 返回的future 是Send
+#[inline]
+pub async fn read_object<R: AsyncRead + Unpin>(reader: &mut R, obj_manager: &ObjectManager) -> io::Result<Arc<dyn IBase>> {
+    let buff = read_frame(reader).await?;
+    let mut data = Data::from_bytes(buff.into());
+    obj_manager.read_core(&mut data).map_err(read_error)
+}
+
+/// 写入一个对象到 AsyncWrite 返回的future 是Send
+#[inline]
+pub async fn write_object<W: AsyncWrite + Unpin, T: IBase + ?Sized>(writer: &mut W, obj_manager: &ObjectManager, arg: &Arc<T>) -> io::Result<()> {
+    let frame = {
+        let mut data = Data::new();
+        obj_manager.write_core(&mut data, arg);
+        Buf::bytes(&*data).to_vec()
+    };
+    write_frame(writer, &frame).await
+}
@@ -0,0 +1,100 @@
+use crate::data::{ModelData, Tables};
+use crate::sync::interface::IBase;
+use ahash::AHashMap;
+use bytes::Bytes;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Read;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// 线程安全对象模型的引用表 Send + Sync
+#[derive(Debug, Default)]
+pub struct SyncTables {
+    pub(crate) w_keep: Option<VecDeque<(usize, Box<dyn Any + Send + Sync>)>>,
+    pub(crate) r_ptr_dict: AHashMap<u32, Arc<dyn IBase>>,
+    pub(crate) ctx: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Tables for SyncTables {
+    type Ptr = Arc<dyn IBase>;
+    type Keep = Box<dyn Any + Send + Sync>;
+
+    #[inline]
+    fn r_ptr_dict(&mut self) -> &mut AHashMap<u32, Self::Ptr> {
+        &mut self.r_ptr_dict
+    }
+
+    #[inline]
+    fn w_keep(&mut self) -> &mut Option<VecDeque<(usize, Self::Keep)>> {
+        &mut self.w_keep
+    }
+}
+
+/// 线程安全 Data 引用表持有 ARC 可以跨线程传递
+/// 读写缓冲区的方法与 pkg::Data 相同
+#[derive(Debug)]
+pub struct Data(crate::data::Data<SyncTables>);
+
+impl Deref for Data {
+    type Target = crate::data::Data<SyncTables>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Data {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Default for Data {
+    #[inline]
+    fn default() -> Self {
+        Data(crate::data::Data::with_buf(Vec::new(), None))
+    }
+}
+
+impl ModelData for Data {
+    type Tables = SyncTables;
+
+    #[inline]
+    fn data_mut(&mut self) -> &mut crate::data::Data<SyncTables> {
+        &mut self.0
+    }
+}
+
+impl Data {
+    #[inline]
+    pub fn new() -> Data {
+        Data::default()
+    }
+
+    #[inline]
+    pub fn with_capacity(cap: usize) -> Data {
+        Data(crate::data::Data::with_buf(Vec::with_capacity(cap), None))
+    }
+
+    /// 从Bytes 创建 读取时不复制 写入时转换为独占buff
+    #[inline]
+    pub fn from_bytes(bytes: Bytes) -> Data {
+        Data(crate::data::Data::with_buf(Vec::new(), Some(bytes)))
+    }
+
+    /// 从reader 读取一帧 bit7长度+数据 长度超过 max_len 返回 InvalidData
+    #[inline]
+    pub fn read_frame_io<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Data> {
+        crate::data::Data::read_frame(reader, max_len).map(Data)
+    }
+
+    /// 创建预留帧头的 Data 写入数据后通过 frame 取得 bit7长度+数据
+    #[inline]
+    pub(crate) fn with_frame_head() -> Data {
+        Data(crate::data::Data::with_frame_head())
+    }
+}
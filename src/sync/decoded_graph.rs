@@ -0,0 +1,34 @@
+use crate::decoded_graph::take_objects;
+use crate::sync::interface::IBase;
+use crate::sync::object_manager::{ObjectManager, ReadObject};
+use crate::sync::data::Data;
+use std::sync::Arc;
+
+/// 线程安全 读取结果和读取时创建的所有对象
+pub type DecodedGraph<T> = crate::decoded_graph::DecodedGraph<T, Arc<dyn IBase>>;
+
+impl ObjectManager {
+    /// 读取一个预设值 并持有读取时创建的所有对象 失败时恢复读取位置
+    #[inline]
+    pub fn read_graph<T: ReadObject + Default>(&self, data: &mut Data) -> Result<DecodedGraph<T>, u32> {
+        let offset = data.get_position();
+        let mut value = T::default();
+        let ret = value.read_(data, self);
+        let objects = take_objects(data);
+        if let Err(err) = ret {
+            data.set_position(offset);
+            return Err(err);
+        }
+        Ok(DecodedGraph::new(value, objects))
+    }
+
+    /// 读取一个 ARC<IBASE> 并持有读取时创建的所有对象
+    #[inline]
+    pub fn read_core_graph(&self, data: &mut Data) -> Result<DecodedGraph<Arc<dyn IBase>>, u32> {
+        let (value, objects) = self.read_graph::<Option<Arc<dyn IBase>>>(data)?.into_parts();
+        match value {
+            None => Err(line!()),
+            Some(value) => Ok(DecodedGraph::new(value, objects)),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::interface::full_typeid;
+use crate::sync::ObjectManager;
+use crate::sync::data::Data;
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::{Arc, Weak};
+
+/// 线程安全 转换为 Any 只为 IBase 实现
+pub trait AsAnyArc: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any_arc(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: IBase> AsAnyArc for T {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any_arc(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// 线程安全 Base接口
+pub trait IBase: Debug + AsAnyArc {
+    fn write(&self, data: &mut Data, o: &ObjectManager);
+    fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32>;
+    fn get_typeid(&self) -> u16;
+
+    /// 命名空间 默认0 与TYPEID组成32位完整TYPEID
+    /// 需要与 IObjectBase::get_static_namespace 一致 调试时 register 会检查
+    #[inline]
+    fn get_namespace(&self) -> u16 {
+        0
+    }
+
+    /// 完整TYPEID (namespace<<16)|typeid
+    #[inline]
+    fn get_full_typeid(&self) -> u32 {
+        full_typeid(self.get_namespace(), self.get_typeid())
+    }
+}
+
+/// 线程安全 OBJECT BASE 接口
+pub trait IObjectBase: IBase + Default {
+    fn get_static_typeid() -> u16;
+
+    /// 命名空间 默认0 需要与 IBase::get_namespace 一致
+    #[inline]
+    fn get_static_namespace() -> u16 {
+        0
+    }
+
+    /// 完整TYPEID (namespace<<16)|typeid
+    #[inline]
+    fn get_static_full_typeid() -> u32 {
+        full_typeid(Self::get_static_namespace(), Self::get_static_typeid())
+    }
+
+    fn new() -> Option<Arc<dyn IBase>>;
+}
+
+pub trait IBaseAsArc {
+    fn cast<T: IObjectBase>(self) -> Result<Arc<T>, Self>
+    where
+        Self: Sized;
+}
+
+impl IBaseAsArc for Arc<dyn IBase> {
+    /// 按实际类型检查后转换 TYPEID 相同但类型不同时返回原值
+    #[inline]
+    fn cast<T: IObjectBase>(self) -> Result<Arc<T>, Self> {
+        if (*self).as_any().is::<T>() {
+            match self.into_any_arc().downcast::<T>() {
+                Ok(v) => Ok(v),
+                Err(_) => unreachable!(),
+            }
+        } else {
+            Err(self)
+        }
+    }
+}
+
+pub trait IBaseAsWeak {
+    fn cast_weak<T: IObjectBase>(self) -> Result<Weak<T>, Self>
+    where
+        Self: Sized;
+}
+
+impl IBaseAsWeak for Weak<dyn IBase> {
+    /// 升级后按实际类型转换 类型不同时返回原值 对象已释放时返回 Weak::new()
+    #[inline]
+    fn cast_weak<T: IObjectBase>(self) -> Result<Weak<T>, Self> {
+        match self.upgrade() {
+            Some(ptr) => match ptr.cast::<T>() {
+                Ok(v) => Ok(Arc::downgrade(&v)),
+                Err(_) => Err(self),
+            },
+            None => Ok(Weak::new()),
+        }
+    }
+}
+
+impl dyn IBase {
+    /// 引用转换为具体类型 类型不同时返回None
+    #[inline]
+    pub fn cast_ref<T: IObjectBase>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+}
+
+/// 按字段顺序生成线程安全 IBase 和 IObjectBase 字段需要实现 ReadOnlyObject 例如 Mutex RwLock 和原子类型
+/// impl_sync_object!(Type = 102 { a, b }) 类型需要实现 Debug + Default
+#[macro_export]
+macro_rules! impl_sync_object {
+    ($($ty:ident = $typeid:literal { $($field:ident),* $(,)? }),+ $(,)?) => {
+        $(
+            impl $crate::sync::IBase for $ty {
+                #[inline]
+                fn write(&self, data: &mut $crate::sync::Data, o: &$crate::sync::ObjectManager) {
+                    $($crate::sync::IObjectManager::write(o, data, &self.$field);)*
+                }
+
+                #[inline]
+                fn read(&self, data: &mut $crate::sync::Data, o: &$crate::sync::ObjectManager) -> Result<(), u32> {
+                    $($crate::sync::IObjectManager::read(o, data, &self.$field)?;)*
+                    Ok(())
+                }
+
+                #[inline]
+                fn get_typeid(&self) -> u16 {
+                    $typeid
+                }
+            }
+
+            impl $crate::sync::IObjectBase for $ty {
+                #[inline]
+                fn get_static_typeid() -> u16 {
+                    $typeid
+                }
+
+                #[inline]
+                fn new() -> Option<std::sync::Arc<dyn $crate::sync::IBase>> {
+                    Some(std::sync::Arc::new(Self::default()))
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,13 @@
+pub mod data;
+pub mod decoded_graph;
+pub mod interface;
+pub mod object_manager;
+pub mod session;
+#[cfg(feature = "async")]
+pub mod codec;
+
+pub use data::*;
+pub use decoded_graph::*;
+pub use interface::*;
+pub use object_manager::*;
+pub use session::*;
@@ -0,0 +1,86 @@
+use crate::sync::data::Data;
+use crate::sync::interface::{IBase, IBaseAsArc, IObjectBase};
+use ahash::AHashMap;
+use bytes::{Buf, BufMut, Bytes};
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::*;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, Weak};
+
+impl_object_manager!(Arc, extends_arc, [+ Send + Sync]);
+
+impl<T: WriteObject> WriteObject for Mutex<T> {
+    #[inline]
+    fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+        self.lock().unwrap_or_else(PoisonError::into_inner).write_(data, obj_manager);
+    }
+}
+
+impl<T: WriteObject> WriteObject for RwLock<T> {
+    #[inline]
+    fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+        self.read().unwrap_or_else(PoisonError::into_inner).write_(data, obj_manager);
+    }
+}
+
+impl WriteObject for AtomicBool {
+    #[inline]
+    fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+        self.load(Ordering::SeqCst).write_(data, obj_manager);
+    }
+}
+
+macro_rules! impl_atomic {
+    ($atomic:ty, $type:ty) => {
+        impl WriteObject for $atomic {
+            #[inline]
+            fn write_(&self, data: &mut Data, obj_manager: &ObjectManager) {
+                self.load(Ordering::SeqCst).write_(data, obj_manager);
+            }
+        }
+
+        impl ReadOnlyObject for $atomic {
+            #[inline]
+            fn read_(&self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+                let mut v = <$type>::default();
+                v.read_(data, obj_manager)?;
+                self.store(v, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_atomic!(AtomicI8, i8);
+impl_atomic!(AtomicU8, u8);
+impl_atomic!(AtomicI16, i16);
+impl_atomic!(AtomicU16, u16);
+impl_atomic!(AtomicI32, i32);
+impl_atomic!(AtomicU32, u32);
+impl_atomic!(AtomicI64, i64);
+impl_atomic!(AtomicU64, u64);
+
+impl ReadOnlyObject for AtomicBool {
+    #[inline]
+    fn read_(&self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+        let mut v = false;
+        v.read_(data, obj_manager)?;
+        self.store(v, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+impl<T: ReadObject> ReadOnlyObject for Mutex<T> {
+    #[inline]
+    fn read_(&self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+        self.lock().unwrap_or_else(PoisonError::into_inner).read_(data, obj_manager)
+    }
+}
+
+impl<T: ReadObject> ReadOnlyObject for RwLock<T> {
+    #[inline]
+    fn read_(&self, data: &mut Data, obj_manager: &ObjectManager) -> Result<(), u32> {
+        self.write().unwrap_or_else(PoisonError::into_inner).read_(data, obj_manager)
+    }
+}
@@ -0,0 +1,140 @@
+use crate::session::{DecodeTable, EncodeTable, DEFAULT_CAPACITY};
+use crate::sync::interface::IBase;
+use crate::sync::object_manager::{ObjectManager, ReadObject, WriteObject};
+use crate::sync::data::{Data, SyncTables};
+use std::sync::Arc;
+
+/// 线程安全 编码会话 与 pkg::EncodeSession 相同 引用表在多个消息间保留 按FIFO淘汰
+pub struct EncodeSession {
+    table: EncodeTable<SyncTables>,
+}
+
+impl Default for EncodeSession {
+    #[inline]
+    fn default() -> Self {
+        EncodeSession::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl EncodeSession {
+    /// 容量为 DEFAULT_CAPACITY
+    #[inline]
+    pub fn new() -> EncodeSession {
+        EncodeSession::default()
+    }
+
+    /// 引用表最多保留 capacity 个对象
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> EncodeSession {
+        EncodeSession {
+            table: EncodeTable::with_capacity(capacity),
+        }
+    }
+
+    /// 写入一个消息
+    #[inline]
+    pub fn write_to<T: WriteObject>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &T) {
+        self.table.write(data, |data| arg.write_(data, obj_manager));
+    }
+
+    /// 写入一个 ARC<IBASE> 消息
+    #[inline]
+    pub fn write_core<T: IBase + ?Sized>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &Arc<T>) {
+        self.table.write(data, |data| obj_manager.write_ptr(data, arg));
+    }
+
+    /// 引用表中的对象数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    /// 清空引用表 需要与 DecodeSession::clear 同时调用
+    #[inline]
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
+
+/// 线程安全 解码会话 与 pkg::DecodeSession 相同 读取失败后会话失效
+pub struct DecodeSession {
+    table: DecodeTable<SyncTables>,
+}
+
+impl Default for DecodeSession {
+    #[inline]
+    fn default() -> Self {
+        DecodeSession::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl DecodeSession {
+    /// 容量为 DEFAULT_CAPACITY
+    #[inline]
+    pub fn new() -> DecodeSession {
+        DecodeSession::default()
+    }
+
+    /// 引用表最多保留 capacity 个对象 需要与 EncodeSession 一致
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> DecodeSession {
+        DecodeSession {
+            table: DecodeTable::with_capacity(capacity),
+        }
+    }
+
+    /// 读取一个消息 失败时恢复读取位置 会话失效
+    /// 不是原子的 失败时 arg 中已读取的部分不恢复 需要原子读取时使用 read_from_atomic
+    #[inline]
+    pub fn read_from<T: ReadObject>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+        self.table.read(data, arg, |arg, data| arg.read_(data, obj_manager))
+    }
+
+    /// 读取一个消息到新创建的值 成功后替换 arg 失败时 arg 不变
+    #[inline]
+    pub fn read_from_atomic<T: ReadObject + Default>(&mut self, obj_manager: &ObjectManager, data: &mut Data, arg: &mut T) -> Result<(), u32> {
+        let mut value = T::default();
+        self.read_from(obj_manager, data, &mut value)?;
+        *arg = value;
+        Ok(())
+    }
+
+    /// 读取一个 ARC<IBASE> 消息
+    #[inline]
+    pub fn read_core(&mut self, obj_manager: &ObjectManager, data: &mut Data) -> Result<Arc<dyn IBase>, u32> {
+        let mut value: Option<Arc<dyn IBase>> = None;
+        self.read_from(obj_manager, data, &mut value)?;
+        match value {
+            None => Err(line!()),
+            Some(p) => Ok(p),
+        }
+    }
+
+    /// 引用表中的对象数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    /// 读取失败后会话失效 clear 后恢复
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.table.is_poisoned()
+    }
+
+    /// 清空引用表 需要与 EncodeSession::clear 同时调用
+    #[inline]
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
@@ -249,6 +249,9 @@ pub fn test_obj_manager_register_create() {
     let path_base = obj_manager.create(2).unwrap();
 
     assert!(obj_manager.create(4).is_none());
+    assert!(obj_manager.is_registered(1));
+    assert!(!obj_manager.is_registered(4));
+    assert_eq!(2, obj_manager.registered().len());
 
     let path = path.cast::<Path>().unwrap();
     let path_base = path_base.cast::<PathBase>().unwrap();
@@ -500,6 +503,22 @@ pub fn test_marco_struct() -> Result<(), u32> {
     Ok(())
 }
 
+#[test]
+pub fn test_registered_where() -> Result<(), u32> {
+    // 注册 struct3 提交的所有结构
+    let obj_manager = ObjectManager::with_registered_where(|reg| reg.module.ends_with("struct3"));
+    assert_eq!(2, obj_manager.registered().len());
+    assert!(obj_manager.is_registered(Foo::get_static_full_typeid()));
+    assert!(obj_manager.is_registered(Foo2::get_static_full_typeid()));
+
+    let mut data = Data::new();
+    let foo: Rc<Foo2> = Rc::new(Foo2::default());
+    obj_manager.write_core(&mut data, &foo);
+    let x = obj_manager.read_core(&mut data)?.cast::<Foo2>().unwrap();
+    assert_eq!(foo.to_string(), x.to_string());
+    Ok(())
+}
+
 #[test]
 pub fn test_x(){
     // 申明 注册结构
@@ -533,4 +552,2057 @@ pub fn test_x(){
     }
 
     println!("read {:?} s",start.elapsed().as_secs_f32());
-}
\ No newline at end of file
+}
+#[test]
+pub fn test_io() -> Result<(), Box<dyn Error>> {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let mut data = Data::new();
+    Write::write_all(&mut data, b"1234567890")?;
+    assert_eq!(10, data.len());
+
+    let mut buff = [0u8; 4];
+    Read::read_exact(&mut data, &mut buff)?;
+    assert_eq!(b"1234", &buff);
+
+    assert_eq!(8, data.seek(SeekFrom::End(-2))?);
+    assert_eq!(2, Read::read(&mut data, &mut buff)?);
+    assert_eq!(b"90", &buff[..2]);
+    assert_eq!(1, data.seek(SeekFrom::Start(1))?);
+    assert_eq!(3, data.seek(SeekFrom::Current(2))?);
+    assert_eq!(3, data.get_position());
+    assert!(data.seek(SeekFrom::Current(100)).is_err());
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+
+    let path_base = PathBase::default();
+    path_base.index.set(100);
+    path_base.path.set(Rc::new(Path::default()));
+    let path_base = Some(Rc::new(path_base));
+
+    let mut pipe = Vec::new();
+    obj_manager.write_into(&mut pipe, &path_base)?;
+    obj_manager.write_into(&mut pipe, &"123".to_string())?;
+
+    let mut reader = &pipe[..];
+    let mut test: Option<Rc<PathBase>> = None;
+    obj_manager.read_out_of(&mut reader, &mut test)?;
+    assert_eq!(path_base, test);
+    let mut test = String::new();
+    obj_manager.read_out_of(&mut reader, &mut test)?;
+    assert_eq!("123", test);
+    assert!(obj_manager.read_out_of(&mut reader, &mut test).is_err());
+
+    // 多字节帧头
+    let mut pipe = Vec::new();
+    let long = "x".repeat(300);
+    obj_manager.write_into(&mut pipe, &long)?;
+    assert_eq!(pipe.len(), 2 + 2 + 300);
+    obj_manager.read_out_of(&mut &pipe[..], &mut test)?;
+    assert_eq!(long, test);
+
+    // 超过最大长度 不分配
+    let err = obj_manager.read_out_of_max(&mut &pipe[..], &mut test, 100).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    let huge = [0xffu8, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+    let err = obj_manager.read_out_of(&mut &huge[..], &mut test).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+pub fn test_async_codec() -> Result<(), Box<dyn Error>> {
+    use bytes::BytesMut;
+    use pkg::codec::{read_frame_max, read_object, write_object, ObjectCodec, PkgCodec};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+    let obj_manager = Rc::new(obj_manager);
+
+    let path_base = PathBase::default();
+    path_base.index.set(100);
+    path_base.path.set(Rc::new(Path::default()));
+    let path_base = Rc::new(path_base);
+
+    let mut codec = ObjectCodec::new(obj_manager.clone());
+    let mut buff = BytesMut::new();
+    codec.encode(path_base.clone(), &mut buff)?;
+    codec.encode(path_base.clone(), &mut buff)?;
+
+    let mut half = buff.split_to(3);
+    assert!(codec.decode(&mut half)?.is_none());
+    half.unsplit(buff);
+    let mut buff = half;
+    for _ in 0..2 {
+        let x = codec.decode(&mut buff)?.unwrap();
+        assert_eq!(path_base, x.cast::<PathBase>().unwrap());
+    }
+    assert!(codec.decode(&mut buff)?.is_none());
+
+    // 超过帧长度上限 读写都返回错误
+    let mut frame = PkgCodec::new();
+    frame.set_max_frame_length(4);
+    let mut buff = BytesMut::new();
+    assert!(frame.encode(&[0u8; 5][..], &mut buff).is_err());
+    frame.encode(&[0u8; 4][..], &mut buff)?;
+    assert_eq!(4, frame.decode(&mut buff)?.unwrap().len());
+    buff.extend_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    assert!(frame.decode(&mut buff).is_err());
+    codec.set_max_frame_length(1);
+    let mut buff = BytesMut::new();
+    assert!(codec.encode(path_base.clone(), &mut buff).is_err());
+
+    let rt = tokio::runtime::Builder::new_current_thread().build()?;
+    rt.block_on(async {
+        let mut pipe = Vec::new();
+        write_object(&mut pipe, &obj_manager, &path_base).await?;
+        write_object(&mut pipe, &obj_manager, &path_base).await?;
+        let mut reader = &pipe[..];
+        for _ in 0..2 {
+            let x = read_object(&mut reader, &obj_manager).await?;
+            assert_eq!(path_base, x.cast::<PathBase>().unwrap());
+        }
+        assert!(read_object(&mut reader, &obj_manager).await.is_err());
+
+        let mut reader = &[0xff, 0xff, 0xff, 0x7f][..];
+        let err = read_frame_max(&mut reader, 1024).await.unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        Ok(())
+    })
+}
+
+#[test]
+pub fn test_dispatcher() -> Result<(), u32> {
+    use pkg::dispatcher::Dispatcher;
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+    obj_manager.register::<Base>();
+
+    #[derive(Default)]
+    struct Ctx {
+        path: i32,
+        path_base: i32,
+        unknown: Vec<u16>,
+    }
+
+    let mut dispatcher = Dispatcher::<Ctx>::new();
+    dispatcher
+        .on::<Path, _>(|p, ctx| {
+            assert_eq!(1000, p.x.get());
+            ctx.path += 1
+        })
+        .on::<PathBase, _>(|p, ctx| ctx.path_base += p.index.get())
+        .on::<PathBase, _>(|_, ctx| ctx.path_base += 1)
+        .fallback(|p, ctx| ctx.unknown.push(p.get_typeid()));
+
+    let mut data = Data::new();
+    let mut ctx = Ctx::default();
+    let path_base = Rc::new(PathBase::default());
+    path_base.index.set(10);
+    let path = Rc::new(Path::default());
+    path.x.set(1000);
+    obj_manager.write_core(&mut data, &path_base);
+    obj_manager.write_core(&mut data, &path);
+    obj_manager.write_core(&mut data, &Rc::new(Base::default()));
+
+    assert!(dispatcher.dispatch(obj_manager.read_core(&mut data)?, &mut ctx));
+    assert!(dispatcher.dispatch(obj_manager.read_core(&mut data)?, &mut ctx));
+    assert!(!dispatcher.dispatch(obj_manager.read_core(&mut data)?, &mut ctx));
+
+    assert_eq!(1, ctx.path);
+    assert_eq!(11, ctx.path_base);
+    assert_eq!(vec![101], ctx.unknown);
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+pub fn test_rpc() -> Result<(), Box<dyn Error>> {
+    use pkg::codec::write_frame;
+    use pkg::rpc::{RpcClient, RpcError, RpcServer, RPC_NAMESPACE};
+    use tokio::io::AsyncWriteExt;
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+    obj_manager.register::<Base>();
+    let obj_manager = Rc::new(obj_manager);
+
+    let mut server = RpcServer::new(obj_manager.clone());
+    server.on::<Path, _>(|req| {
+        if req.x.get() < 0 {
+            return Err(RpcError::new(100, "x < 0"));
+        }
+        let resp = PathBase::default();
+        resp.index.set(req.x.get() + req.y.get());
+        resp.path.set(req);
+        Ok(Rc::new(resp))
+    });
+    let client = RpcClient::new(obj_manager.clone());
+
+    let make_path = |x: i32, y: i32| {
+        let path = Path::default();
+        path.x.set(x);
+        path.y.set(y);
+        Rc::new(path)
+    };
+
+    let rt = tokio::runtime::Builder::new_current_thread().build()?;
+    rt.block_on(async {
+        let (client_io, server_io) = tokio::io::duplex(64);
+        let (mut server_reader, mut server_writer) = tokio::io::split(server_io);
+        let (mut client_reader, mut client_writer) = tokio::io::split(client_io);
+
+        let server = async { server.serve(&mut server_reader, &mut server_writer).await };
+        let client = async {
+            // 无法解析的帧 服务端回复错误后继续处理
+            write_frame(&mut client_writer, &[0xff]).await?;
+            assert!(client.recv(&mut client_reader).await.is_err());
+
+            let (frame, ok) = client.call(&make_path(1, 2));
+            write_frame(&mut client_writer, Buf::bytes(&frame)).await?;
+            let (frame, err) = client.call(&make_path(-1, 2));
+            write_frame(&mut client_writer, Buf::bytes(&frame)).await?;
+            let (frame, not_found) = client.call(&Rc::new(Base::default()));
+            write_frame(&mut client_writer, Buf::bytes(&frame)).await?;
+
+            while client.pending() > 0 {
+                client.recv(&mut client_reader).await?;
+            }
+            client_writer.shutdown().await?;
+
+            let ok = ok.await.unwrap().cast::<PathBase>().unwrap();
+            assert_eq!(3, ok.index.get());
+            assert_eq!(make_path(1, 2), ok.path.get().unwrap());
+            assert_eq!(RpcError::new(100, "x < 0"), err.await.unwrap_err());
+            assert_eq!(RpcError::NOT_FOUND, not_found.await.unwrap_err().code.get());
+            assert_eq!(RPC_NAMESPACE, RpcError::default().get_namespace());
+
+            // 响应无法解析时 请求以 BAD_FRAME 完成 不再等待
+            let (_, bad) = client.call(&make_path(1, 2));
+            let (_, unknown) = client.call(&make_path(1, 2));
+            assert_eq!(2, client.pending());
+            let mut frame = Data::new();
+            frame.write_bit7(4u32);
+            frame.put_u8(1);
+            frame.put_u8(0xff);
+            assert!(client.on_response(frame.as_slice()).is_err());
+            let mut frame = Data::new();
+            frame.write_bit7(5u32);
+            frame.put_u8(9);
+            assert!(client.on_response(frame.as_slice()).is_err());
+            assert_eq!(0, client.pending());
+            assert_eq!(RpcError::BAD_FRAME, bad.await.unwrap_err().code.get());
+            assert_eq!(RpcError::BAD_FRAME, unknown.await.unwrap_err().code.get());
+            Ok::<_, std::io::Error>(())
+        };
+
+        let (server, client) = tokio::join!(server, client);
+        server?;
+        client?;
+        Ok(())
+    })
+}
+
+#[test]
+pub fn test_zero_copy() -> Result<(), u32> {
+    use bytes::Bytes;
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+
+    let mut data = Data::new();
+    data.write_str_bit7("hello");
+    data.write_buff_bit7(b"world");
+    obj_manager.write_to(&mut data, &Bytes::from_static(b"bytes"));
+    let base = Rc::new(Base::default());
+    base.name.replace("base".to_string());
+    obj_manager.write_core(&mut data, &base);
+
+    let frame = Bytes::copy_from_slice(data.as_slice());
+    let mut data = Data::from_bytes(frame.clone());
+    assert_eq!(Some("hello"), data.get_str_bit7_ref());
+    assert_eq!(Some(&b"world"[..]), data.get_buff_bit7_ref());
+
+    let mut bytes = Bytes::new();
+    obj_manager.read_from(&mut data, &mut bytes)?;
+    assert_eq!(&b"bytes"[..], &bytes[..]);
+    let range = frame.as_ptr() as usize..frame.as_ptr() as usize + frame.len();
+    assert!(range.contains(&(bytes.as_ptr() as usize)));
+
+    let x = obj_manager.read_core(&mut data)?.cast::<Base>().unwrap();
+    assert_eq!(base, x);
+    assert_eq!(0, data.remaining());
+
+    data.write_str_bit7("append");
+    assert_eq!(Some("append".to_string()), data.get_str_bit7());
+    assert_eq!(frame.len() + 7, data.len());
+    Ok(())
+}
+
+#[test]
+pub fn test_data_ref() {
+    use pkg::DataRef;
+
+    // 从栈上的切片读取 返回的字符串借用原始数据
+    let mut data = Data::new();
+    data.write_str_bit7("hello");
+    data.write_bit7(-300i32);
+    data.write_buff_bit7(b"world");
+    data.put_u16_le(7);
+    let mut buff = [0u8; 32];
+    buff[..data.len()].copy_from_slice(data.as_slice());
+    let len = data.len();
+
+    let mut reader = DataRef::new(&buff[..len]);
+    let hello = reader.get_str_bit7_ref().unwrap();
+    assert_eq!("hello", hello);
+    assert_eq!(buff.as_ptr() as usize + 1, hello.as_ptr() as usize);
+    assert_eq!(Some((2, -300)), reader.read_bit7_i32());
+    assert_eq!(Some(&b"world"[..]), reader.get_buff_bit7_ref());
+    assert_eq!(7, reader.get_u16_le());
+    assert_eq!(0, reader.remaining());
+    assert_eq!(None, reader.read_bit7_u32());
+
+    // 长度超过剩余数据时返回None 读取位置不变
+    let buff = [0xff, 0xff, 0xff, 0x0f, b'a'];
+    let mut reader = DataRef::from(&buff[..]);
+    assert_eq!(None, reader.get_buff_bit7_ref());
+    assert_eq!(0, reader.get_position());
+    assert_eq!(None, reader.read_ref(6));
+}
+
+#[test]
+pub fn test_register_conflict() {
+    #[derive(Debug, Default)]
+    struct Other;
+
+    impl IBase for Other {
+        fn write(&self, _: &mut Data, _: &ObjectManager) {}
+
+        fn read(&self, _: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+            Ok(())
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Other::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Other {
+        fn get_static_typeid() -> u16 {
+            1
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Other))
+        }
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+
+    let err = obj_manager.try_register::<Other>().unwrap_err();
+    assert!(err.contains("Path") && err.contains("Other"), "{}", err);
+    assert!(obj_manager.create(1).unwrap().cast::<Path>().is_ok());
+
+    let ids = obj_manager.registered();
+    assert_eq!(vec![1, 2], ids.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+    assert!(ids[1].1.ends_with("PathBase"));
+
+    let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || obj_manager.register::<Other>()));
+    assert!(ret.is_err());
+}
+
+#[test]
+pub fn test_sync_struct() -> Result<(), u32> {
+    use pkg::sync::{Data, IBaseAsArc, IObjectManager, ObjectManager};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<SyncBase>();
+    obj_manager.register::<SyncFly>();
+    let obj_manager = Arc::new(obj_manager);
+    assert_send_sync(&obj_manager);
+
+    let base = Arc::new(SyncBase::default());
+    base.id.store(1000, Ordering::SeqCst);
+    *base.name.write().unwrap() = "test ppp".to_string();
+    *base.data.lock().unwrap() = vec![1, 2, 3, 4, 5];
+
+    let fly = Arc::new(SyncFly::default());
+    fly.alive.store(true, Ordering::SeqCst);
+    *fly.x.lock().unwrap() = 1.1;
+    *fly.y.write().unwrap() = 2.2;
+    *fly.rc.write().unwrap() = Some(base.clone());
+    *fly.wk.write().unwrap() = Some(Arc::downgrade(&base));
+    *fly.vec.lock().unwrap() = vec![base.clone(), base.clone()];
+    fly.hash.lock().unwrap().insert(1, base.clone());
+
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &fly);
+
+    // 解码用的 Data 可以传给其他线程
+    let mut data = Data::from_bytes(bytes::Bytes::copy_from_slice(data.as_slice()));
+    assert_send_sync(&data);
+    let handle = {
+        let obj_manager = obj_manager.clone();
+        std::thread::spawn(move || {
+            let x = obj_manager.read_core(&mut data).unwrap();
+            assert_send_sync(&x);
+            x
+        })
+    };
+
+    let m = handle.join().unwrap().cast::<SyncFly>().unwrap();
+    assert!(m.alive.load(Ordering::SeqCst));
+    assert_eq!(1.1, *m.x.lock().unwrap());
+    assert_eq!(2.2, *m.y.read().unwrap());
+    let rc = m.rc.read().unwrap().clone().unwrap();
+    assert_eq!(1000, rc.id.load(Ordering::SeqCst));
+    assert_eq!("test ppp", *rc.name.read().unwrap());
+    assert_eq!(vec![1, 2, 3, 4, 5], *rc.data.lock().unwrap());
+    assert!(Arc::ptr_eq(&rc, &m.wk.read().unwrap().clone().unwrap().upgrade().unwrap()));
+    for x in m.vec.lock().unwrap().iter() {
+        assert!(Arc::ptr_eq(&rc, x));
+    }
+    assert!(Arc::ptr_eq(&rc, &m.hash.lock().unwrap()[&1]));
+
+    // 与 Rc 模型的格式一致
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &base);
+    let mut data = pkg::Data::from_bytes(bytes::Bytes::copy_from_slice(data.as_slice()));
+    let mut rc_obj_manager = pkg::ObjectManager::new();
+    rc_obj_manager.register::<Base>();
+    let x = rc_obj_manager.read_core(&mut data)?.cast::<Base>().unwrap();
+    assert_eq!(1000, x.id.get());
+    assert_eq!("test ppp", *x.name.borrow());
+    Ok(())
+}
+
+#[test]
+pub fn test_sync_parity() -> Result<(), u32> {
+    use pkg::sync::{Data, DecodeSession, EncodeSession, IBase, IBaseAsArc, IObjectBase, IObjectManager, ObjectManager};
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::{Arc, Mutex, RwLock};
+
+    #[derive(Debug, Default)]
+    struct OldBase {
+        id: AtomicI32,
+    }
+
+    pkg::impl_sync_object!(OldBase = 2101 { id });
+
+    #[derive(Debug, Default)]
+    struct SyncDog {
+        base: SyncBase,
+        bark: AtomicI32,
+    }
+
+    impl IBase for SyncDog {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            self.base.write(data, o);
+            o.write(data, &self.bark);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            self.base.read(data, o)?;
+            o.read(data, &self.bark)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for SyncDog {
+        fn get_static_typeid() -> u16 {
+            2102
+        }
+
+        fn new() -> Option<Arc<dyn IBase>> {
+            Some(Arc::new(Self::default()))
+        }
+    }
+
+    trait Named: IBase {
+        fn name(&self) -> String;
+    }
+
+    impl Named for SyncBase {
+        fn name(&self) -> String {
+            self.name.read().unwrap().clone()
+        }
+    }
+
+    fn make_base(id: i32, name: &str) -> Arc<SyncBase> {
+        let base = Arc::new(SyncBase::default());
+        base.id.store(id, Ordering::SeqCst);
+        *base.name.write().unwrap() = name.to_string();
+        base
+    }
+
+    // 带上下文的工厂
+    #[derive(Default)]
+    struct Pool {
+        created: AtomicI32,
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register_with(101, |pool: &Pool| -> Arc<dyn IBase> {
+        pool.created.fetch_add(1, Ordering::SeqCst);
+        Arc::new(SyncBase::default())
+    });
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &make_base(7, "ctx"));
+    obj_manager.write_core(&mut data, &make_base(7, "ctx"));
+    let pool = Arc::new(Pool::default());
+    let x = obj_manager.read_core_with(&mut data, pool.clone())?.cast::<SyncBase>().unwrap();
+    assert_eq!(7, x.id.load(Ordering::SeqCst));
+    assert_eq!(1, pool.created.load(Ordering::SeqCst));
+    assert!(obj_manager.read_core(&mut data).is_err());
+    assert!(obj_manager.read_core_with(&mut data, Arc::new(1i32)).is_err());
+    let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        obj_manager.register_with(101, |_: &Pool| -> Arc<dyn IBase> { Arc::new(SyncBase::default()) })
+    }));
+    assert!(ret.is_err());
+
+    // 别名 旧结构迁移为新类型 字段和共享引用都能读取到
+    let mut old_manager = ObjectManager::new();
+    old_manager.register::<OldBase>();
+    let old = Arc::new(OldBase::default());
+    old.id.store(5, Ordering::SeqCst);
+    let mut data = Data::new();
+    old_manager.write_to(&mut data, &vec![old.clone(), old]);
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<SyncBase>();
+    obj_manager.alias::<OldBase, _>(101, |old| make_base(old.id.load(Ordering::SeqCst), "migrated"));
+    assert!(obj_manager.is_alias(2101));
+    assert!(obj_manager.try_register::<OldBase>().is_err());
+    let mut v: Vec<Arc<SyncBase>> = Vec::new();
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(5, v[0].id.load(Ordering::SeqCst));
+    assert_eq!("migrated", *v[0].name.read().unwrap());
+    assert!(Arc::ptr_eq(&v[0], &v[1]));
+
+    // 读取到用户 trait object
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register_as::<SyncBase, dyn Named>(|v| v);
+    obj_manager.register::<SyncFly>();
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(make_base(1, "named")));
+    let mut v: Option<Arc<dyn Named>> = None;
+    obj_manager.read_dyn(&mut data, &mut v)?;
+    assert_eq!("named", v.unwrap().name());
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(Arc::new(SyncFly::default())));
+    let mut v: Option<Arc<dyn Named>> = None;
+    assert!(obj_manager.read_dyn(&mut data, &mut v).is_err());
+
+    // 继承 view 和 read_kind_of
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.extends::<SyncDog, SyncBase>(|v| &v.base);
+    obj_manager.register::<SyncFly>();
+    assert!(obj_manager.is_kind_of(2102, 101));
+    assert!(!obj_manager.is_kind_of(101, 2102));
+    let dog = Arc::new(SyncDog::default());
+    *dog.base.name.write().unwrap() = "rex".to_string();
+    dog.bark.store(3, Ordering::SeqCst);
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(dog as Arc<dyn IBase>));
+    let mut v: Option<Arc<dyn IBase>> = None;
+    obj_manager.read_kind_of::<SyncBase>(&mut data, &mut v)?;
+    let v = v.unwrap();
+    assert_eq!(2102, v.get_full_typeid());
+    assert_eq!("rex", *obj_manager.view::<SyncBase>(&*v).unwrap().name.read().unwrap());
+    assert!(obj_manager.view::<SyncFly>(&*v).is_none());
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(Arc::new(SyncFly::default()) as Arc<dyn IBase>));
+    let mut v: Option<Arc<dyn IBase>> = None;
+    assert!(obj_manager.read_kind_of::<SyncBase>(&mut data, &mut v).is_err());
+
+    // 子类型读取到 Option<Arc<Base>> 字段
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.extends_arc::<SyncDog, SyncBase>(|dog| {
+        Arc::new(SyncBase {
+            id: AtomicI32::new(dog.base.id.load(Ordering::SeqCst)),
+            name: RwLock::new(dog.base.name.read().unwrap().clone()),
+            data: Mutex::new(dog.base.data.lock().unwrap().clone()),
+        })
+    });
+    obj_manager.register::<SyncFly>();
+    let dog = Arc::new(SyncDog::default());
+    dog.base.id.store(9, Ordering::SeqCst);
+    let dog = dog as Arc<dyn IBase>;
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![Some(dog.clone()), Some(dog)]);
+    let mut v: Vec<Option<Arc<SyncBase>>> = Vec::new();
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(2, v.len());
+    assert!(v.iter().all(|base| base.as_ref().unwrap().id.load(Ordering::SeqCst) == 9));
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(Arc::new(SyncFly::default()) as Arc<dyn IBase>));
+    let mut v: Option<Arc<SyncBase>> = None;
+    assert!(obj_manager.read_from(&mut data, &mut v).is_err());
+
+    // 会话 之后的消息只写入引用
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<SyncBase>();
+    obj_manager.register::<SyncFly>();
+    let base = make_base(2, "shared");
+    let mut encoder = EncodeSession::with_capacity(1);
+    let mut decoder = DecodeSession::with_capacity(1);
+    let mut lens = Vec::new();
+    let mut read = Vec::new();
+    for obj in [&base, &base, &make_base(3, "other"), &base].iter() {
+        let mut data = Data::new();
+        encoder.write_core(&obj_manager, &mut data, obj);
+        lens.push(data.len());
+        read.push(decoder.read_core(&obj_manager, &mut data)?.cast::<SyncBase>().unwrap());
+        assert_eq!(1, encoder.len());
+        assert_eq!(1, decoder.len());
+    }
+    assert!(lens[1] < lens[0]);
+    assert_eq!(lens[0], lens[3]);
+    assert!(Arc::ptr_eq(&read[0], &read[1]));
+    assert!(!Arc::ptr_eq(&read[0], &read[3]));
+    assert_eq!("shared", *read[3].name.read().unwrap());
+    let mut data = Data::new();
+    data.write(&[1, 2]);
+    assert!(decoder.read_core(&obj_manager, &mut data).is_err());
+    assert!(decoder.is_poisoned());
+    decoder.clear();
+    assert!(!decoder.is_poisoned());
+
+    // 只有 Weak 引用的对象 read_graph 读取后保持有效
+    let fly = Arc::new(SyncFly::default());
+    let b = make_base(5, "weak");
+    *fly.wk.write().unwrap() = Some(Arc::downgrade(&b));
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &fly);
+    let buff = data.as_slice().to_vec();
+    let x = obj_manager.read_core(&mut data)?.cast::<SyncFly>().unwrap();
+    assert!(x.wk.read().unwrap().as_ref().unwrap().upgrade().is_none());
+    let mut data = Data::new();
+    data.write(&buff);
+    let graph = obj_manager.read_core_graph(&mut data)?;
+    assert_eq!(2, graph.objects().len());
+    let x = (*graph).clone().cast::<SyncFly>().unwrap();
+    let wk = x.wk.read().unwrap().as_ref().unwrap().clone();
+    assert_eq!(5, wk.upgrade().unwrap().id.load(Ordering::SeqCst));
+    drop(graph);
+    assert!(wk.upgrade().is_none());
+
+    // bool
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![true, false]);
+    let mut v: Vec<bool> = Vec::new();
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(vec![true, false], v);
+    let mut data = Data::new();
+    data.write(&[1, 2]);
+    assert!(obj_manager.read_from(&mut data, &mut v).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+pub fn test_sync_codec() -> Result<(), Box<dyn Error>> {
+    use pkg::sync::codec::{read_object, write_object};
+    use pkg::sync::{IBaseAsArc, ObjectManager};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<SyncBase>();
+    let obj_manager = Arc::new(obj_manager);
+
+    let base = Arc::new(SyncBase::default());
+    base.id.store(7, Ordering::SeqCst);
+
+    let rt = tokio::runtime::Builder::new_current_thread().build()?;
+    rt.block_on(async {
+        let mut pipe = Vec::new();
+        let write = write_object(&mut pipe, &obj_manager, &base);
+        assert_send(&write);
+        write.await?;
+
+        let mut reader = &pipe[..];
+        let read = read_object(&mut reader, &obj_manager);
+        assert_send(&read);
+        let x = read.await?.cast::<SyncBase>().unwrap();
+        assert_eq!(7, x.id.load(Ordering::SeqCst));
+        Ok(())
+    })
+}
+
+#[test]
+pub fn test_auto_register() {
+    let obj_manager = ObjectManager::with_all_registered();
+    for typeid in &[1, 2, 101, 102, 1000, 1001] {
+        assert!(obj_manager.is_registered(*typeid), "{}", typeid);
+    }
+    assert!(obj_manager.create(102).unwrap().cast::<Fly>().is_ok());
+
+    let obj_manager = ObjectManager::with_registered_where(|reg| reg.module.ends_with("struct1"));
+    assert_eq!(vec![1, 2], obj_manager.registered().iter().map(|(id, _)| *id).collect::<Vec<_>>());
+}
+
+#[test]
+pub fn test_namespace() -> Result<(), u32> {
+    use pkg::interface::{full_typeid, namespace_of};
+
+    const NS: u16 = namespace_of("game.combat");
+
+    #[derive(Debug, Default)]
+    struct Hit {
+        damage: Cell<i32>,
+    }
+
+    impl IBase for Hit {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.damage);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.damage)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+
+        fn get_namespace(&self) -> u16 {
+            Self::get_static_namespace()
+        }
+    }
+
+    impl IObjectBase for Hit {
+        fn get_static_typeid() -> u16 {
+            1
+        }
+
+        fn get_static_namespace() -> u16 {
+            NS
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    assert_eq!(NS, namespace_of("game.combat"));
+    assert_ne!(NS, namespace_of("game.chat"));
+    assert_ne!(0, NS);
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<Hit>();
+    assert!(obj_manager.is_registered(1));
+    assert!(obj_manager.is_registered(full_typeid(NS, 1)));
+
+    // 命名空间0的格式不变
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &Rc::new(Path::default()));
+    assert_eq!(&[1, 1], &data.as_slice()[..2]);
+
+    let hit = Rc::new(Hit::default());
+    hit.damage.set(99);
+    obj_manager.write_core(&mut data, &hit);
+    let x = obj_manager.read_core(&mut data)?;
+    assert!(x.cast::<Path>().is_ok());
+    let x = obj_manager.read_core(&mut data)?;
+    assert_eq!(full_typeid(NS, 1), x.get_full_typeid());
+    assert!(x.clone().cast::<Path>().is_err());
+    assert_eq!(99, x.cast::<Hit>().unwrap().damage.get());
+
+    // 只重载 get_static_namespace 调试时注册失败
+    #[derive(Debug, Default)]
+    struct Miss;
+
+    impl IBase for Miss {
+        fn write(&self, _: &mut Data, _: &ObjectManager) {}
+
+        fn read(&self, _: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+            Ok(())
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Miss {
+        fn get_static_typeid() -> u16 {
+            2
+        }
+
+        fn get_static_namespace() -> u16 {
+            NS
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self))
+        }
+    }
+
+    if cfg!(debug_assertions) {
+        let ret = std::panic::catch_unwind(|| ObjectManager::new().register::<Miss>());
+        assert!(ret.is_err());
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_register_with_context() -> Result<(), u32> {
+    #[derive(Default)]
+    struct Pool {
+        created: Cell<i32>,
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register_with(101, |pool: &Pool| -> Rc<dyn IBase> {
+        pool.created.set(pool.created.get() + 1);
+        Rc::new(Base::default())
+    });
+
+    let base = Rc::new(Base::default());
+    base.id.set(7);
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &base);
+    obj_manager.write_core(&mut data, &base);
+    obj_manager.write_core(&mut data, &base);
+
+    let pool = Rc::new(Pool::default());
+    let x = obj_manager.read_core_with(&mut data, pool.clone())?;
+    assert_eq!(7, x.cast::<Base>().unwrap().id.get());
+    assert_eq!(1, pool.created.get());
+
+    // 没有上下文或上下文类型不对 读取失败
+    assert!(obj_manager.read_core(&mut data).is_err());
+    data.set_position(0);
+    let _ = obj_manager.read_core_with(&mut data, pool.clone())?;
+    let _ = obj_manager.read_core_with(&mut data, pool.clone())?;
+    assert!(obj_manager.read_core_with(&mut data, Rc::new(1i32)).is_err());
+    assert_eq!(3, pool.created.get());
+
+    // 工厂返回的对象TYPEID不一致 创建失败 调试时 panic
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register_with(102, |_: &Pool| -> Rc<dyn IBase> { Rc::new(Base::default()) });
+    let pool = Pool::default();
+    let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| obj_manager.create_with(102, Some(&pool)).is_none()));
+    assert!(if cfg!(debug_assertions) { ret.is_err() } else { ret.unwrap() });
+
+    // 同一函数中的两个闭包类型名相同 仍然按冲突处理
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register_with(101, |_: &Pool| -> Rc<dyn IBase> { Rc::new(Base::default()) });
+    let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        obj_manager.register_with(101, |_: &Pool| -> Rc<dyn IBase> { Rc::new(Base::default()) })
+    }));
+    assert!(ret.is_err());
+    assert!(obj_manager.try_register::<Base>().is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_alias() -> Result<(), u32> {
+    #[derive(Debug, Default)]
+    struct OldHit {
+        hp: Cell<i32>,
+    }
+
+    impl IBase for OldHit {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.hp);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.hp)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for OldHit {
+        fn get_static_typeid() -> u16 {
+            1000
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct NewHit {
+        hp: Cell<i64>,
+        note: RefCell<String>,
+    }
+
+    impl IBase for NewHit {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.hp);
+            o.write(data, &self.note);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.hp)?;
+            o.read(data, &self.note)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for NewHit {
+        fn get_static_typeid() -> u16 {
+            1050
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    let mut old_manager = ObjectManager::new();
+    old_manager.register::<OldHit>();
+    let hit = Rc::new(OldHit::default());
+    hit.hp.set(12);
+    let mut data = Data::new();
+    old_manager.write_core(&mut data, &hit);
+    old_manager.write_to(&mut data, &vec![hit.clone(), hit]);
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<NewHit>();
+    obj_manager.alias(1050, |old: &OldHit| -> Rc<dyn IBase> {
+        Rc::new(NewHit {
+            hp: Cell::new(old.hp.get() as i64),
+            note: RefCell::new("migrated".to_string()),
+        })
+    });
+    assert!(obj_manager.is_alias(1000));
+    assert!(obj_manager.try_register::<OldHit>().is_err());
+    assert!(obj_manager.try_alias(1051, |_: &OldHit| -> Rc<dyn IBase> { Rc::new(NewHit::default()) }).is_err());
+
+    let x = obj_manager.read_core(&mut data)?.cast::<NewHit>().unwrap();
+    assert_eq!(12, x.hp.get());
+    assert_eq!("migrated", *x.note.borrow());
+
+    // 共享引用迁移后仍指向同一个新对象
+    let mut v: Vec<Rc<NewHit>> = Vec::new();
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(2, v.len());
+    assert!(Rc::ptr_eq(&v[0], &v[1]));
+    assert_eq!(12, v[1].hp.get());
+    Ok(())
+}
+
+#[test]
+pub fn test_read_dyn() -> Result<(), u32> {
+    trait Shape: IBase {
+        fn area(&self) -> f32;
+    }
+
+    #[derive(Debug, Default)]
+    struct Circle {
+        r: Cell<f32>,
+    }
+
+    impl IBase for Circle {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.r);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.r)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Circle {
+        fn get_static_typeid() -> u16 {
+            2001
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    impl Shape for Circle {
+        fn area(&self) -> f32 {
+            3.0 * self.r.get() * self.r.get()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Canvas {
+        a: RefCell<Option<Rc<dyn Shape>>>,
+        b: RefCell<Option<Rc<dyn Shape>>>,
+    }
+
+    impl IBase for Canvas {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.a);
+            o.write(data, &self.b);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read_dyn(data, &mut self.a.borrow_mut())?;
+            o.read_dyn(data, &mut self.b.borrow_mut())
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Canvas {
+        fn get_static_typeid() -> u16 {
+            2000
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Canvas>();
+    obj_manager.register_as::<Circle, dyn Shape>(|v| v);
+
+    let circle: Rc<dyn Shape> = Rc::new(Circle { r: Cell::new(2.0) });
+    let canvas = Rc::new(Canvas::default());
+    *canvas.a.borrow_mut() = Some(circle.clone());
+    *canvas.b.borrow_mut() = Some(circle);
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &canvas);
+
+    let x = obj_manager.read_core(&mut data)?.cast::<Canvas>().unwrap();
+    let a = x.a.borrow().clone().unwrap();
+    let b = x.b.borrow().clone().unwrap();
+    assert_eq!(12.0, a.area());
+    assert!(Rc::ptr_eq(&a, &b));
+
+    // 没有实现 Shape 的类型
+    assert!(obj_manager.upcast::<dyn Shape>(canvas.clone()).is_err());
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(canvas));
+    let mut shape: Option<Rc<dyn Shape>> = None;
+    assert!(obj_manager.read_dyn(&mut data, &mut shape).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_safe_cast() {
+    use pkg::interface::{AsAny, IBaseAsWeak};
+
+    // 与 Base 相同的TYPEID
+    #[derive(Debug, Default)]
+    struct FakeBase;
+
+    impl IBase for FakeBase {
+        fn write(&self, _: &mut Data, _: &ObjectManager) {}
+
+        fn read(&self, _: &mut Data, _: &ObjectManager) -> Result<(), u32> {
+            Ok(())
+        }
+
+        fn get_typeid(&self) -> u16 {
+            101
+        }
+    }
+
+    let fake: Rc<dyn IBase> = Rc::new(FakeBase);
+    assert_eq!(Base::get_static_full_typeid(), fake.get_full_typeid());
+    assert!(fake.clone().cast::<Base>().is_err());
+    assert!(fake.cast_ref::<Base>().is_none());
+
+    let base = Rc::new(Base::default());
+    base.id.set(5);
+    let obj: Rc<dyn IBase> = base.clone();
+    assert_eq!(5, obj.cast_ref::<Base>().unwrap().id.get());
+    assert!(obj.cast_ref::<Fly>().is_none());
+
+    let weak = Rc::downgrade(&obj);
+    assert!(weak.clone().cast_weak::<Fly>().is_err());
+    let weak = weak.cast_weak::<Base>().unwrap();
+    assert!(Rc::ptr_eq(&base, &weak.upgrade().unwrap()));
+
+    // 已释放的 Weak 无法确认类型 转换为 Weak::new()
+    let weak = Rc::downgrade(&obj);
+    drop(obj);
+    drop(base);
+    assert!(weak.clone().cast_weak::<Fly>().unwrap().upgrade().is_none());
+    assert!(weak.cast_weak::<Base>().unwrap().upgrade().is_none());
+    let empty: std::rc::Weak<dyn IBase> = std::rc::Weak::<Base>::new();
+    assert!(empty.cast_weak::<Fly>().unwrap().upgrade().is_none());
+
+    // as_any 只对 IBase 类型生效 Rc 自动解引用到对象
+    let base = Rc::new(Base::default());
+    assert!(base.as_any().is::<Base>());
+
+    let obj: std::sync::Arc<dyn pkg::sync::IBase> = std::sync::Arc::new(SyncBase::default());
+    assert!(obj.cast_ref::<SyncBase>().is_some());
+    assert!(obj.cast_ref::<SyncFly>().is_none());
+}
+
+#[test]
+pub fn test_extends() -> Result<(), u32> {
+    #[derive(Debug, Default)]
+    struct Animal {
+        name: RefCell<String>,
+    }
+
+    impl IBase for Animal {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.name);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.name)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Animal {
+        fn get_static_typeid() -> u16 {
+            3001
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Dog {
+        base: Animal,
+        bark: Cell<i32>,
+    }
+
+    impl IBase for Dog {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            self.base.write(data, o);
+            o.write(data, &self.bark);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            self.base.read(data, o)?;
+            o.read(data, &self.bark)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Dog {
+        fn get_static_typeid() -> u16 {
+            3002
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Puppy {
+        base: Dog,
+    }
+
+    impl IBase for Puppy {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            self.base.write(data, o);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            self.base.read(data, o)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+    }
+
+    impl IObjectBase for Puppy {
+        fn get_static_typeid() -> u16 {
+            3003
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.extends::<Dog, Animal>(|v| &v.base);
+    obj_manager.extends::<Puppy, Dog>(|v| &v.base);
+    obj_manager.register::<Path>();
+    assert!(obj_manager.is_kind_of(3003, 3001));
+    assert!(!obj_manager.is_kind_of(3001, 3002));
+
+    let puppy = Rc::new(Puppy::default());
+    puppy.base.base.name.replace("rex".to_string());
+    puppy.base.bark.set(3);
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(puppy as Rc<dyn IBase>));
+
+    let mut v: Option<Rc<dyn IBase>> = None;
+    obj_manager.read_kind_of::<Animal>(&mut data, &mut v)?;
+    let v = v.unwrap();
+    assert_eq!(3003, v.get_full_typeid());
+    assert_eq!("rex", *obj_manager.view::<Animal>(&*v).unwrap().name.borrow());
+    assert_eq!(3, obj_manager.view::<Dog>(&*v).unwrap().bark.get());
+    assert!(obj_manager.view::<Path>(&*v).is_none());
+
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(Rc::new(Path::default()) as Rc<dyn IBase>));
+    let mut v: Option<Rc<dyn IBase>> = None;
+    assert!(obj_manager.read_kind_of::<Animal>(&mut data, &mut v).is_err());
+
+    // 子类型读取到 Option<Rc<Base>> 字段 共享引用保留同一个子类型对象
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.extends_rc::<Fly, Base>(|fly| {
+        let base = fly.base.borrow();
+        let name = base.name.borrow().clone();
+        let data = base.data.borrow().clone();
+        Rc::new(Base {
+            id: Cell::new(base.id.get()),
+            name: RefCell::new(name),
+            data: RefCell::new(data),
+        })
+    });
+    assert!(obj_manager.is_kind_of(Fly::get_static_full_typeid(), Base::get_static_full_typeid()));
+
+    let fly = Rc::new(Fly::default());
+    fly.base.borrow().id.set(9);
+    fly.base.borrow().name.replace("fly".to_string());
+    let fly = fly as Rc<dyn IBase>;
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![Some(fly.clone()), Some(fly)]);
+    let mut v: Vec<Option<Rc<Base>>> = Vec::new();
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(2, v.len());
+    for base in v.iter() {
+        let base = base.as_ref().unwrap();
+        assert_eq!(9, base.id.get());
+        assert_eq!("fly", *base.name.borrow());
+    }
+
+    // 没有注册继承的类型仍然失败
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(Rc::new(Path::default()) as Rc<dyn IBase>));
+    let mut v: Option<Rc<Base>> = None;
+    assert!(obj_manager.read_from(&mut data, &mut v).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_session() -> Result<(), u32> {
+    use pkg::session::{DecodeSession, EncodeSession, DEFAULT_CAPACITY};
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+    let base = Rc::new(Base::default());
+    base.name.replace("shared".to_string());
+    let other = Rc::new(Base::default());
+
+    let mut encoder = EncodeSession::new();
+    let mut decoder = DecodeSession::new();
+    let mut data = Data::new();
+    encoder.write_core(&obj_manager, &mut data, &base);
+    let first_len = data.len();
+    let x = decoder.read_core(&obj_manager, &mut data)?;
+
+    // 第二个消息只写入引用
+    let mut data = Data::new();
+    encoder.write_core(&obj_manager, &mut data, &base);
+    assert!(data.len() < first_len);
+    let y = decoder.read_core(&obj_manager, &mut data)?;
+    assert!(Rc::ptr_eq(&x, &y));
+    assert_eq!("shared", *y.cast::<Base>().unwrap().name.borrow());
+    assert_eq!(1, encoder.len());
+    assert_eq!(1, decoder.len());
+
+    // 容量为1 写入新对象后淘汰旧对象 再次写入时完整发送
+    let mut encoder = EncodeSession::with_capacity(1);
+    let mut decoder = DecodeSession::with_capacity(1);
+    let mut lens = Vec::new();
+    for obj in [&base, &other, &base, &base].iter() {
+        let mut data = Data::new();
+        encoder.write_core(&obj_manager, &mut data, obj);
+        lens.push(data.len());
+        let x = decoder.read_core(&obj_manager, &mut data)?;
+        assert_eq!(obj.name.borrow().clone(), *x.cast::<Base>().unwrap().name.borrow());
+        assert_eq!(1, encoder.len());
+        assert_eq!(1, decoder.len());
+    }
+    assert_eq!(lens[0], lens[2]);
+    assert!(lens[3] < lens[2]);
+
+    // 按首次写入顺序淘汰 再次引用 base 不会保留它
+    let third = Rc::new(Base::default());
+    let mut encoder = EncodeSession::with_capacity(2);
+    let mut decoder = DecodeSession::with_capacity(2);
+    let mut lens = Vec::new();
+    for obj in [&base, &other, &base, &third, &base].iter() {
+        let mut data = Data::new();
+        encoder.write_core(&obj_manager, &mut data, obj);
+        lens.push(data.len());
+        let x = decoder.read_core(&obj_manager, &mut data)?;
+        assert_eq!(obj.name.borrow().clone(), *x.cast::<Base>().unwrap().name.borrow());
+    }
+    assert!(lens[2] < lens[0]);
+    assert_eq!(lens[0], lens[4]);
+
+    // 默认容量有上限
+    let mut encoder = EncodeSession::new();
+    let list: Vec<Rc<Base>> = (0..DEFAULT_CAPACITY + 1).map(|_| Rc::new(Base::default())).collect();
+    encoder.write_to(&obj_manager, &mut Data::new(), &list);
+    assert_eq!(DEFAULT_CAPACITY, encoder.len());
+
+    encoder.clear();
+    decoder.clear();
+    assert!(encoder.is_empty() && decoder.is_empty());
+    Ok(())
+}
+
+#[test]
+pub fn test_delta() -> Result<(), u32> {
+    fn make_fly(x: f32, id: i32) -> Fly {
+        let fly = Fly::default();
+        fly.x.set(x);
+        fly.y.set(2.0);
+        fly.base.borrow().name.replace("fly".to_string());
+        let base = Rc::new(Base::default());
+        base.id.set(id);
+        fly.vec.borrow_mut().push(Some(base.clone()));
+        fly.hash.borrow_mut().insert(1, Some(base));
+        fly
+    }
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+    obj_manager.register::<Fly>();
+
+    let old = make_fly(1.0, 10);
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &old);
+    let full_len = data.len();
+    let target = Fly::default();
+    target.read(&mut data, &obj_manager)?;
+
+    // 只有x变化
+    let new = make_fly(5.0, 10);
+    let mut data = Data::new();
+    obj_manager.write_delta(&mut data, &old, &new);
+    assert!(data.len() < 8);
+    obj_manager.apply_delta(&mut data, &target)?;
+    assert_eq!(5.0, target.x.get());
+    assert_eq!(2.0, target.y.get());
+
+    // 嵌套对象和集合的变化
+    let old = new;
+    let new = make_fly(5.0, 11);
+    new.hash.borrow_mut().insert(2, None);
+    let mut data = Data::new();
+    obj_manager.write_delta(&mut data, &old, &new);
+    assert!(data.len() < full_len);
+    obj_manager.apply_delta(&mut data, &target)?;
+    assert_eq!(11, target.vec.borrow()[0].as_ref().unwrap().id.get());
+    assert_eq!(11, target.hash.borrow()[&1].as_ref().unwrap().id.get());
+    assert!(target.hash.borrow()[&2].is_none());
+    assert_eq!("fly", *target.base.borrow().name.borrow());
+
+    let mut data = Data::new();
+    obj_manager.write_delta(&mut data, &new, &new);
+    assert_eq!(1, data.len());
+
+    // Rc 字段原地修改 old 需要是深拷贝的快照
+    let fly = Rc::new(make_fly(5.0, 11));
+    fly.rc.replace(Some(Rc::new(Base::default())));
+    let snapshot = obj_manager.deep_clone(&(fly.clone() as Rc<dyn IBase>))?.value().clone().cast::<Fly>().unwrap();
+    let target = obj_manager.deep_clone(&(fly.clone() as Rc<dyn IBase>))?.value().clone().cast::<Fly>().unwrap();
+    fly.rc.borrow().as_ref().unwrap().id.set(42);
+    assert!(obj_manager.changed(&fly.rc, &snapshot.rc));
+    assert!(!obj_manager.changed(&fly.rc, &fly.rc));
+    let mut data = Data::new();
+    obj_manager.write_delta(&mut data, &*snapshot, &*fly);
+    obj_manager.apply_delta(&mut data, &*target)?;
+    assert_eq!(42, target.rc.borrow().as_ref().unwrap().id.get());
+
+    // bool 字段 和字段数上限
+    let flag = Cell::new(false);
+    assert!(obj_manager.changed(&Cell::new(true), &flag));
+    let mut data = Data::new();
+    obj_manager.write_field_delta(&mut data, &Cell::new(true), &flag);
+    obj_manager.apply_field_delta(&mut data, &flag)?;
+    assert!(flag.get());
+    assert_eq!(1 << 63, pkg::delta::field_bit(63));
+    assert!(std::panic::catch_unwind(|| pkg::delta::field_bit(64)).is_err());
+
+    // 长度超过剩余数据 返回错误 不分配
+    let vec = RefCell::new(vec![1i32, 2]);
+    let mut data = Data::new();
+    data.write_bit7(u64::MAX >> 1);
+    data.put_u8(0);
+    assert!(obj_manager.apply_field_delta(&mut data, &vec).is_err());
+    assert_eq!(vec![1, 2], *vec.borrow());
+    Ok(())
+}
+
+#[test]
+pub fn test_track_changes() -> Result<(), u32> {
+    use pkg::delta::{DirtyMask, ITrackChanges};
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+
+    let player = Player::default();
+    let remote = Player::default();
+    remote.set_name("remote");
+    remote.take_dirty();
+    player.set_hp(80);
+    let base = Rc::new(Base::default());
+    base.id.set(3);
+    player.set_target(base);
+    assert!(player.dirty_mask().is_dirty(0) && !player.dirty_mask().is_dirty(1) && player.dirty_mask().is_dirty(2));
+
+    let mut data = Data::new();
+    obj_manager.write_dirty(&mut data, &player);
+    assert_eq!(0, player.dirty_mask().get());
+    obj_manager.read_dirty(&mut data, &remote)?;
+    assert_eq!(80, remote.hp());
+    assert_eq!("remote", remote.name());
+    assert_eq!(3, remote.target().unwrap().id.get());
+
+    player.set_name("p1");
+    let mut data = Data::new();
+    obj_manager.write_dirty(&mut data, &player);
+    obj_manager.read_dirty(&mut data, &remote)?;
+    assert_eq!("p1", remote.name());
+
+    // 没有改变时只写入掩码
+    let mut data = Data::new();
+    obj_manager.write_dirty(&mut data, &player);
+    assert_eq!(1, data.len());
+
+    // 差异掩码与脏字段掩码的序号相同
+    let old = Player::default();
+    let new = Player::default();
+    new.set_name("p2");
+    let mut data = Data::new();
+    obj_manager.write_delta(&mut data, &old, &new);
+    assert_eq!(new.dirty_mask().get(), obj_manager.read_delta_mask(&mut data)?);
+
+    // 超过64个字段的序号 panic
+    assert!(std::panic::catch_unwind(|| DirtyMask::default().mark(64)).is_err());
+    assert!(std::panic::catch_unwind(|| DirtyMask::default().is_dirty(64)).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_deep_clone() -> Result<(), u32> {
+    let b1 = Rc::new(Base::default());
+    b1.id.set(1);
+    let b2 = Rc::new(Base::default());
+    b2.id.set(2);
+    let fly = Rc::new(Fly::default());
+    fly.x.set(3.0);
+    fly.base.borrow().name.replace("fly".to_string());
+    *fly.rc.borrow_mut() = Some(b1.clone());
+    *fly.wk.borrow_mut() = Some(Rc::downgrade(&b1));
+    fly.vec.borrow_mut().push(Some(b1.clone()));
+    fly.vec.borrow_mut().push(Some(b2.clone()));
+    fly.hash.borrow_mut().insert(1, Some(b2.clone()));
+
+    // 只被 Weak 引用的对象
+    let b3 = Rc::new(Base::default());
+    b3.id.set(3);
+    fly.vec_wk.borrow_mut().push(Some(Rc::downgrade(&b3)));
+
+    // 内存复制不需要注册
+    let obj_manager = ObjectManager::new();
+    let graph = obj_manager.deep_clone(&(fly.clone() as Rc<dyn IBase>))?;
+    assert_eq!(4, graph.objects().len());
+    let x = graph.value().clone().cast::<Fly>().unwrap();
+    assert!(!Rc::ptr_eq(&fly, &x));
+    assert_eq!(3.0, x.x.get());
+    assert_eq!("fly", *x.base.borrow().name.borrow());
+
+    let rc = x.rc.borrow().clone().unwrap();
+    assert!(!Rc::ptr_eq(&rc, &b1));
+    assert_eq!(1, rc.id.get());
+    assert!(Rc::ptr_eq(&rc, x.vec.borrow()[0].as_ref().unwrap()));
+    assert!(Rc::ptr_eq(&rc, &x.wk.borrow().as_ref().unwrap().upgrade().unwrap()));
+    let c2 = x.vec.borrow()[1].clone().unwrap();
+    assert_eq!(2, c2.id.get());
+    assert!(Rc::ptr_eq(&c2, x.hash.borrow()[&1].as_ref().unwrap()));
+
+    let c3 = x.vec_wk.borrow()[0].as_ref().unwrap().upgrade().unwrap();
+    assert!(!Rc::ptr_eq(&c3, &b3));
+    assert_eq!(3, c3.id.get());
+
+    // 修改复制不影响原对象
+    rc.id.set(10);
+    assert_eq!(1, b1.id.get());
+
+    // 没有实现 IDeepClone 的类型通过序列化复制
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    let path = Rc::new(Path::default());
+    let x = obj_manager.deep_clone(&(path.clone() as Rc<dyn IBase>))?;
+    assert!(x.value().clone().cast::<Path>().is_ok());
+
+    // 内部的 Rc<dyn IBase> 没有实现 IDeepClone 时返回错误 不共享原对象
+    #[derive(Debug, Default)]
+    struct Holder {
+        obj: RefCell<Option<Rc<dyn IBase>>>,
+    }
+
+    impl IBase for Holder {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.obj);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.obj)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            Self::get_static_typeid()
+        }
+
+        pkg::deep_clone_dyn!();
+    }
+
+    impl IObjectBase for Holder {
+        fn get_static_typeid() -> u16 {
+            4002
+        }
+
+        fn new() -> Option<Rc<dyn IBase>> {
+            Some(Rc::new(Self::default()))
+        }
+    }
+
+    impl pkg::deep_clone::IDeepClone for Holder {
+        fn deep_clone_into(&self, target: &Self, map: &mut pkg::deep_clone::CloneMap) {
+            map.clone_field(&self.obj, &target.obj);
+        }
+    }
+
+    let holder = Rc::new(Holder::default());
+    holder.obj.replace(Some(b1.clone()));
+    let x = obj_manager.deep_clone(&(holder.clone() as Rc<dyn IBase>))?;
+    let obj = x.value().clone().cast::<Holder>().unwrap().obj.borrow().clone().unwrap();
+    assert!(!Rc::ptr_eq(&obj, &(b1.clone() as Rc<dyn IBase>)));
+    holder.obj.replace(Some(path));
+    assert!(obj_manager.deep_clone(&(holder.clone() as Rc<dyn IBase>)).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_graph_eq() -> Result<(), u32> {
+    use pkg::graph_eq::graph_eq;
+
+    fn make_fly(shared: bool, id: i32) -> Fly {
+        let fly = Fly::default();
+        let b1 = Rc::new(Base::default());
+        b1.id.set(1);
+        let b2 = if shared { b1.clone() } else { Rc::new(Base::default()) };
+        b2.id.set(1);
+        *fly.rc.borrow_mut() = Some(b1.clone());
+        *fly.wk.borrow_mut() = Some(Rc::downgrade(&b1));
+        fly.vec.borrow_mut().push(Some(b2));
+        let b3 = Rc::new(Base::default());
+        b3.id.set(id);
+        fly.hash.borrow_mut().insert(7, Some(b3));
+        fly
+    }
+
+    assert_eq!(Ok(()), graph_eq(&make_fly(true, 2), &make_fly(true, 2)));
+    assert_eq!(Ok(()), graph_eq(&make_fly(false, 2), &make_fly(false, 2)));
+    assert_eq!(Err("vec[0]: shared reference differs".to_string()), graph_eq(&make_fly(true, 2), &make_fly(false, 2)));
+    assert_eq!(Err("hash[7].id: 2 != 3".to_string()), graph_eq(&make_fly(true, 2), &make_fly(true, 3)));
+
+    // 复制后的图与原图同构
+    let fly = Rc::new(make_fly(true, 2));
+    let x = ObjectManager::new().deep_clone(&(fly.clone() as Rc<dyn IBase>))?;
+    assert_eq!(Ok(()), graph_eq(&*fly, &*x.value().clone().cast::<Fly>().unwrap()));
+
+    // NaN 与 NaN 相同
+    let (a, b) = (make_fly(true, 2), make_fly(true, 2));
+    a.x.set(f32::NAN);
+    b.x.set(f32::NAN);
+    assert_eq!(Ok(()), graph_eq(&a, &b));
+
+    // HashMap 按键的顺序比较
+    for i in 0..16 {
+        let (a, b) = (make_fly(true, 2), make_fly(true, 2));
+        for k in [40 + i, 20, 30 + i] {
+            a.hash.borrow_mut().insert(k, Some(Rc::new(Base::default())));
+            let v = Rc::new(Base::default());
+            v.id.set(k);
+            b.hash.borrow_mut().insert(k, Some(v));
+        }
+        assert_eq!(Err("hash[20].id: 0 != 20".to_string()), graph_eq(&a, &b));
+    }
+
+    // Rc<dyn IBase> 内部的共享关系与外层使用同一个对应表
+    #[derive(Debug, Default)]
+    struct Node {
+        base: RefCell<Option<Rc<Base>>>,
+        obj: RefCell<Option<Rc<dyn IBase>>>,
+    }
+
+    impl IBase for Node {
+        fn write(&self, data: &mut Data, o: &ObjectManager) {
+            o.write(data, &self.base);
+            o.write(data, &self.obj);
+        }
+
+        fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+            o.read(data, &self.base)?;
+            o.read(data, &self.obj)
+        }
+
+        fn get_typeid(&self) -> u16 {
+            4003
+        }
+
+        pkg::graph_eq_dyn!();
+    }
+
+    impl pkg::graph_eq::IGraphEq for Node {
+        fn graph_eq_fields(&self, other: &Self, ctx: &mut pkg::graph_eq::GraphEqContext) -> Result<(), String> {
+            ctx.field("base", &self.base, &other.base)?;
+            ctx.field("obj", &self.obj, &other.obj)
+        }
+    }
+
+    fn make_node(shared: bool) -> Node {
+        let base = Rc::new(Base::default());
+        let inner = Fly::default();
+        let rc = if shared { base.clone() } else { Rc::new(Base::default()) };
+        inner.rc.replace(Some(rc));
+        let node = Node::default();
+        node.base.replace(Some(base));
+        node.obj.replace(Some(Rc::new(inner)));
+        node
+    }
+
+    assert_eq!(Ok(()), graph_eq(&make_node(true), &make_node(true)));
+    assert_eq!(Err("obj.rc: shared reference differs".to_string()), graph_eq(&make_node(true), &make_node(false)));
+
+    // 没有实现 IGraphEq 的类型不能比较
+    let (a, b) = (Node::default(), Node::default());
+    a.obj.replace(Some(Rc::new(Path::default())));
+    b.obj.replace(Some(Rc::new(Path::default())));
+    assert!(graph_eq(&a, &b).unwrap_err().contains("not supported"));
+    Ok(())
+}
+
+#[test]
+pub fn test_reflect() -> Result<(), u32> {
+    use pkg::reflect::{Reflect, Value};
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Fly>();
+    let obj = obj_manager.create(Fly::get_static_typeid() as u32).unwrap();
+    let reflect = obj.as_reflect().unwrap();
+    let names: Vec<&str> = reflect.fields().iter().map(|f| f.name).collect();
+    assert_eq!(names[..3], ["base", "x", "y"]);
+    assert!(reflect.fields()[1].type_name.ends_with("Cell<f32>"));
+
+    reflect.set_field("x", Value::Float(2.5)).unwrap();
+    assert_eq!(Some(Value::Float(2.5)), reflect.get_field("x"));
+
+    let b = Rc::new(Base::default());
+    b.id.set(3);
+    reflect.set_field("rc", Value::Object(b.clone())).unwrap();
+    reflect.set_field("wk", Value::Object(b.clone())).unwrap();
+    reflect.set_field("hash", Value::Map(vec![(Value::Int(7), Value::Object(b.clone()))])).unwrap();
+    obj.clone().cast::<Fly>().unwrap().base.borrow().data.replace(vec![1, 2]);
+    // 缺少字段时返回错误 不重置其他字段
+    assert!(reflect.set_field("base", Value::Struct(vec![("id".to_string(), Value::Int(9))])).is_err());
+    match reflect.get_field("base") {
+        Some(Value::Struct(mut fields)) => {
+            fields[0].1 = Value::Int(9);
+            fields[1].1 = Value::Str("x".to_string());
+            reflect.set_field("base", Value::Struct(fields)).unwrap();
+        }
+        v => panic!("{:?}", v),
+    }
+
+    let fly = obj.clone().cast::<Fly>().unwrap();
+    assert_eq!(fly.x.get(), 2.5);
+    assert!(Rc::ptr_eq(fly.rc.borrow().as_ref().unwrap(), &b));
+    assert!(Rc::ptr_eq(&fly.wk.borrow().as_ref().unwrap().upgrade().unwrap(), &b));
+    assert!(Rc::ptr_eq(fly.hash.borrow()[&7].as_ref().unwrap(), &b));
+    assert_eq!(fly.base.borrow().id.get(), 9);
+    assert_eq!(*fly.base.borrow().name.borrow(), "x");
+    assert_eq!(*fly.base.borrow().data.borrow(), vec![1, 2]);
+
+    // 通过 Object 继续访问
+    match reflect.get_field("rc") {
+        Some(Value::Object(p)) => assert_eq!(Some(Value::Int(3)), p.as_reflect().unwrap().get_field("id")),
+        v => panic!("{:?}", v),
+    }
+
+    // 类型不匹配 越界 字段不存在
+    assert!(reflect.set_field("x", Value::Str("a".to_string())).is_err());
+    assert!(b.set_field("id", Value::Int(i64::MAX)).is_err());
+    assert!(reflect.set_field("rc", Value::Object(obj.clone())).is_err());
+    assert!(reflect.set_field("none", Value::None).is_err());
+    assert_eq!(None, reflect.get_field("none"));
+    assert_eq!(fly.x.get(), 2.5);
+    Ok(())
+}
+
+#[test]
+pub fn test_decoded_graph() -> Result<(), u32> {
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+    obj_manager.register::<Fly>();
+
+    // 只有 Weak 引用的对象
+    let fly = Rc::new(Fly::default());
+    let b = Rc::new(Base::default());
+    b.id.set(5);
+    *fly.wk.borrow_mut() = Some(Rc::downgrade(&b));
+    fly.vec_wk.borrow_mut().push(Some(Rc::downgrade(&b)));
+    let mut data = Data::new();
+    obj_manager.write_core(&mut data, &fly);
+    let buff = data.as_slice().to_vec();
+
+    let x = obj_manager.read_core(&mut data)?.cast::<Fly>().unwrap();
+    assert!(x.wk.borrow().as_ref().unwrap().upgrade().is_none());
+
+    let mut data = Data::new();
+    data.write(&buff);
+    let graph = obj_manager.read_core_graph(&mut data)?;
+    assert_eq!(2, graph.objects().len());
+    let x = (*graph).clone().cast::<Fly>().unwrap();
+    let wk = x.wk.borrow().as_ref().unwrap().clone();
+    assert_eq!(5, wk.upgrade().unwrap().id.get());
+    assert!(Rc::ptr_eq(&wk.upgrade().unwrap(), &x.vec_wk.borrow()[0].as_ref().unwrap().upgrade().unwrap()));
+
+    // 释放后失效
+    drop(graph);
+    assert!(wk.upgrade().is_none());
+
+    // 读取预设值
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(fly.clone()));
+    let graph = obj_manager.read_graph::<Option<Rc<Fly>>>(&mut data)?;
+    let x = graph.value().as_ref().unwrap();
+    assert_eq!(5, x.wk.borrow().as_ref().unwrap().upgrade().unwrap().id.get());
+    let (_, objects) = graph.into_parts();
+    assert!(objects[0].clone().cast::<Fly>().is_ok());
+    Ok(())
+}
+
+#[test]
+pub fn test_read_rollback() -> Result<(), u32> {
+    use pkg::session::{DecodeSession, EncodeSession};
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+    obj_manager.register::<Fly>();
+    let fly = Rc::new(Fly::default());
+    fly.x.set(1.5);
+    *fly.rc.borrow_mut() = Some(Rc::new(Base::default()));
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(fly.clone()));
+    let buff = data.as_slice().to_vec();
+
+    // 没有注册 Base 读取到 rc 时失败
+    let mut reader = ObjectManager::new();
+    reader.register::<Fly>();
+    let mut data = Data::new();
+    data.write(&buff);
+    let target = Some(Rc::new(Fly::default()));
+    let mut value = target.clone();
+    assert!(reader.read_from_atomic(&mut data, &mut value).is_err());
+    assert_eq!(0, data.get_position());
+    // 读取到新创建的值 失败时 target 不变
+    assert!(Rc::ptr_eq(value.as_ref().unwrap(), target.as_ref().unwrap()));
+    assert_eq!(0.0, target.as_ref().unwrap().x.get());
+    assert!(reader.read_core(&mut data).is_err());
+    assert_eq!(0, data.get_position());
+    assert!(reader.read_graph::<Option<Rc<Fly>>>(&mut data).is_err());
+    assert_eq!(0, data.get_position());
+
+    // 数据不完整 返回错误 不越界
+    let mut v: Option<u32> = Some(1);
+    let mut data = Data::new();
+    assert!(obj_manager.read_from(&mut data, &mut v).is_err());
+    assert_eq!(Some(1), v);
+    let mut full = Data::new();
+    obj_manager.write_to(&mut full, &vec![Some(1u32)]);
+    let mut data = Data::new();
+    data.write(&full.as_slice()[..2]);
+    let mut v: Vec<Option<u32>> = Vec::new();
+    assert!(obj_manager.read_from(&mut data, &mut v).is_err());
+    assert_eq!(0, data.get_position());
+    assert!(v.is_empty());
+    data.set_position(1);
+    assert!(data.read_ref(usize::MAX).is_none());
+    assert!(data.read_bytes(usize::MAX).is_none());
+    let mut data = Data::new();
+    data.write(&buff);
+
+    // 原地读取 失败时恢复读取位置 已读取的字段不恢复
+    assert!(reader.read_from_mode(&mut data, &mut value, pkg::ReadMode::Merge).is_err());
+    assert_eq!(0, data.get_position());
+    assert_eq!(1.5, target.as_ref().unwrap().x.get());
+
+    // 重新读取
+    target.as_ref().unwrap().x.set(0.0);
+    obj_manager.read_from_atomic(&mut data, &mut value)?;
+    assert!(!Rc::ptr_eq(value.as_ref().unwrap(), target.as_ref().unwrap()));
+    assert_eq!(1.5, value.as_ref().unwrap().x.get());
+    assert_eq!(0.0, target.as_ref().unwrap().x.get());
+
+    // 会话读取失败后失效 clear 后恢复
+    let mut enc = EncodeSession::new();
+    let mut dec = DecodeSession::new();
+    let b = Rc::new(Base::default());
+    let mut data = Data::new();
+    enc.write_to(&obj_manager, &mut data, &Some(b.clone()));
+    let mut x: Option<Rc<Base>> = None;
+    dec.read_from(&obj_manager, &mut data, &mut x)?;
+    assert_eq!(1, dec.len());
+
+    let mut data = Data::new();
+    enc.write_to(&obj_manager, &mut data, &vec![Some(fly.clone()), Some(fly.clone())]);
+    let mut v: Vec<Option<Rc<Fly>>> = Vec::new();
+    assert!(dec.read_from(&reader, &mut data, &mut v).is_err());
+    assert_eq!(0, data.get_position());
+    assert!(dec.is_poisoned());
+    assert!(dec.read_from(&obj_manager, &mut data, &mut v).is_err());
+    assert!(v.is_empty());
+
+    enc.clear();
+    dec.clear();
+    let mut data = Data::new();
+    enc.write_to(&obj_manager, &mut data, &vec![Some(fly.clone()), Some(fly.clone())]);
+    dec.read_from(&obj_manager, &mut data, &mut v)?;
+    assert_eq!(2, dec.len());
+    assert!(Rc::ptr_eq(v[0].as_ref().unwrap(), v[1].as_ref().unwrap()));
+
+    // 差异和脏字段读取失败时恢复读取位置
+    let mut data = Data::new();
+    data.write_bit7(1u64);
+    assert!(obj_manager.apply_delta(&mut data, &*fly).is_err());
+    assert_eq!(0, data.get_position());
+    Ok(())
+}
+
+#[test]
+pub fn test_read_mode() -> Result<(), u32> {
+    use pkg::ReadMode;
+
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+    obj_manager.register::<Fly>();
+
+    let fly = Rc::new(Fly::default());
+    fly.x.set(2.0);
+    fly.vec.borrow_mut().push(Some(Rc::new(Base::default())));
+    fly.hash.borrow_mut().insert(1, None);
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(fly.clone()));
+    let buff = data.as_slice().to_vec();
+
+    let make_target = || {
+        let target = Rc::new(Fly::default());
+        target.vec.borrow_mut().push(None);
+        target.hash.borrow_mut().insert(2, None);
+        target
+    };
+
+    // Merge 原地读取 Vec 追加 Map 插入
+    let target = make_target();
+    let mut value = Some(target.clone());
+    let mut data = Data::new();
+    data.write(&buff);
+    obj_manager.read_from_mode(&mut data, &mut value, ReadMode::Merge)?;
+    assert!(Rc::ptr_eq(value.as_ref().unwrap(), &target));
+    assert_eq!(2.0, target.x.get());
+    assert_eq!(2, target.vec.borrow().len());
+    assert_eq!(2, target.hash.borrow().len());
+
+    // Replace 新建对象 原对象不变
+    let target = make_target();
+    let mut value = Some(target.clone());
+    let mut data = Data::new();
+    data.write(&buff);
+    obj_manager.read_from_mode(&mut data, &mut value, ReadMode::Replace)?;
+    assert_eq!(ReadMode::Merge, data.read_mode());
+    let x = value.unwrap();
+    assert!(!Rc::ptr_eq(&x, &target));
+    assert_eq!(0.0, target.x.get());
+    assert_eq!(1, target.vec.borrow().len());
+    assert_eq!(1, x.vec.borrow().len());
+    assert!(x.vec.borrow()[0].is_some());
+    assert_eq!(vec![1], x.hash.borrow().keys().copied().collect::<Vec<_>>());
+
+    // Replace 读取到集合 清空原有内容
+    let mut v = vec![1u32, 2, 3];
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![4u32]);
+    obj_manager.read_from_mode(&mut data, &mut v, ReadMode::Replace)?;
+    assert_eq!(vec![4], v);
+
+    // read_from_atomic 读取到新创建的值 与 Replace 相同 read_from 按 Merge 原地读取
+    let mut v = vec![1u32, 2, 3];
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![4u32]);
+    obj_manager.read_from_atomic(&mut data, &mut v)?;
+    assert_eq!(vec![4], v);
+    data.set_position(0);
+    obj_manager.read_from(&mut data, &mut v)?;
+    assert_eq!(vec![4, 4], v);
+
+    let mut m = BTreeMap::new();
+    m.insert(1i64, "a".to_string());
+    let mut data = Data::new();
+    let mut n = BTreeMap::new();
+    n.insert(2i64, "b".to_string());
+    obj_manager.write_to(&mut data, &n);
+    obj_manager.read_from_mode(&mut data, &mut m, ReadMode::Replace)?;
+    assert_eq!(n, m);
+
+    // sync 相同
+    let sync_manager = pkg::sync::ObjectManager::new();
+    let mut data = pkg::sync::Data::new();
+    pkg::sync::IObjectManager::write_to(&sync_manager, &mut data, &vec![4u32]);
+    let mut v = vec![1u32];
+    sync_manager.read_from_mode(&mut data, &mut v, ReadMode::Replace)?;
+    assert_eq!(vec![4], v);
+
+    // 集合读取失败时原有内容不变
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &vec![4u32, 5]);
+    let vec_len = data.len();
+    let mut n = HashMap::new();
+    n.insert(2i32, "b".to_string());
+    obj_manager.write_to(&mut data, &n);
+    let buff = data.as_slice().to_vec();
+    for mode in [ReadMode::Replace, ReadMode::Merge] {
+        let mut v = vec![1u32, 2, 3];
+        let mut data = Data::new();
+        data.write(&buff[..vec_len - 1]);
+        assert!(obj_manager.read_from_mode(&mut data, &mut v, mode).is_err());
+        assert_eq!(vec![1, 2, 3], v);
+        let mut data = pkg::sync::Data::new();
+        data.write(&buff[..vec_len - 1]);
+        assert!(sync_manager.read_from_mode(&mut data, &mut v, mode).is_err());
+        assert_eq!(vec![1, 2, 3], v);
+
+        let mut m = HashMap::new();
+        m.insert(1i32, "a".to_string());
+        let mut data = Data::new();
+        data.write(&buff[vec_len..buff.len() - 1]);
+        assert!(obj_manager.read_from_mode(&mut data, &mut m, mode).is_err());
+        assert_eq!(vec![1], m.keys().copied().collect::<Vec<_>>());
+        let mut data = pkg::sync::Data::new();
+        data.write(&buff[vec_len..buff.len() - 1]);
+        assert!(sync_manager.read_from_mode(&mut data, &mut m, mode).is_err());
+        assert_eq!(vec![1], m.keys().copied().collect::<Vec<_>>());
+    }
+
+    // Merge 时类型不同的已有对象不原地读取
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Path>();
+    obj_manager.register::<PathBase>();
+    let path_base = Rc::new(PathBase::default());
+    path_base.index.set(7);
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Some(path_base));
+    let buff = data.as_slice().to_vec();
+
+    let path = Rc::new(Path::default());
+    let mut value = Some(path.clone());
+    let mut data = Data::new();
+    data.write(&buff);
+    assert!(obj_manager.read_from_mode(&mut data, &mut value, ReadMode::Merge).is_err());
+    assert!(Rc::ptr_eq(value.as_ref().unwrap(), &path));
+
+    let mut value: Option<Rc<dyn IBase>> = Some(path.clone());
+    let mut data = Data::new();
+    data.write(&buff);
+    obj_manager.read_from_mode(&mut data, &mut value, ReadMode::Merge)?;
+    assert_eq!(7, value.unwrap().cast::<PathBase>().unwrap().index.get());
+    assert_eq!(Path::default(), *path);
+    Ok(())
+}
+
+#[test]
+pub fn test_read_mut() -> Result<(), u32> {
+    let mut obj_manager = ObjectManager::new();
+    obj_manager.register::<Base>();
+
+    let shared = Rc::new(Base::default());
+    shared.id.set(8);
+    let mut dict = HashMap::new();
+    dict.insert(3, PlainChild { id: 3, tags: vec!["c".to_string()] });
+    let path = PlainPath {
+        x: 1,
+        y: 0.5,
+        name: Some("p".to_string()),
+        child: Some(Box::new(PlainChild { id: 2, tags: vec!["a".to_string(), "b".to_string()] })),
+        list: vec![PlainChild::default(), PlainChild { id: 4, tags: vec![] }],
+        dict,
+        shared: Some(shared),
+    };
+
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &path);
+    let mut x = PlainPath::default();
+    obj_manager.read_from(&mut data, &mut x)?;
+    assert_eq!(1, x.x);
+    assert_eq!(0.5, x.y);
+    assert_eq!(path.name, x.name);
+    assert_eq!(path.child, x.child);
+    assert_eq!(path.list, x.list);
+    assert_eq!(path.dict, x.dict);
+    assert_eq!(8, x.shared.as_ref().unwrap().id.get());
+
+    // Box 顶层值
+    let mut data = Data::new();
+    obj_manager.write_to(&mut data, &Box::new(PlainChild { id: 5, tags: vec!["d".to_string()] }));
+    let mut b: Box<PlainChild> = Box::default();
+    obj_manager.read_from(&mut data, &mut b)?;
+    assert_eq!(5, b.id);
+    assert_eq!(vec!["d".to_string()], b.tags);
+    Ok(())
+}
@@ -1,7 +1,13 @@
 mod struct1;
 mod struct2;
 mod struct3;
+mod struct4;
+mod struct5;
+mod struct6;
 
 pub use struct1::*;
 pub use struct2::*;
 pub use struct3::*;
+pub use struct4::*;
+pub use struct5::*;
+pub use struct6::*;
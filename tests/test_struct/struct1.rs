@@ -78,3 +78,5 @@ impl IObjectBase for PathBase {
         }))
     }
 }
+
+pkg::register_type!(Path, PathBase);
@@ -1,5 +1,6 @@
 use pkg::interface::{IBase, IObjectBase};
 use pkg::object_manager::IObjectManager;
+use pkg::reflect::Reflect;
 use pkg::{Data, ObjectManager};
 use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, HashMap};
@@ -29,6 +30,14 @@ impl IBase for Base {
     fn get_typeid(&self) -> u16 {
         Self::get_static_typeid()
     }
+
+    pkg::deep_clone_dyn!();
+
+    pkg::graph_eq_dyn!();
+
+    fn as_reflect(&self) -> Option<&dyn Reflect> {
+        Some(self)
+    }
 }
 
 impl IObjectBase for Base {
@@ -94,6 +103,14 @@ impl pkg::interface::IBase for Fly {
     fn get_typeid(&self) -> u16 {
         Self::get_static_typeid()
     }
+
+    pkg::deep_clone_dyn!();
+
+    pkg::graph_eq_dyn!();
+
+    fn as_reflect(&self) -> Option<&dyn Reflect> {
+        Some(self)
+    }
 }
 
 impl pkg::interface::IObjectBase for Fly {
@@ -105,3 +122,25 @@ impl pkg::interface::IObjectBase for Fly {
         Some(Rc::new(Self::default()))
     }
 }
+
+pkg::impl_deep_clone_fields!(
+    Base { id, name, data },
+    Fly { base, x, y, rc, wk, vec, vec_wk, hash, hash_wk, treemap, treemap_wk },
+);
+
+pkg::impl_graph_eq_fields!(
+    Base { id, name, data },
+    Fly { base, x, y, rc, wk, vec, vec_wk, hash, hash_wk, treemap, treemap_wk },
+);
+
+pkg::impl_delta_fields!(
+    Base { id, name, data },
+    Fly { base, x, y, rc, wk, vec, vec_wk, hash, hash_wk, treemap, treemap_wk },
+);
+
+pkg::impl_reflect!(
+    Base { id, name, data },
+    Fly { base, x, y, rc, wk, vec, vec_wk, hash, hash_wk, treemap, treemap_wk },
+);
+
+pkg::register_type!(Base, Fly);
@@ -22,4 +22,4 @@ pub struct Foo2 {
     pub ptr: RefCell<Option<Rc<Foo>>>,
 }
 
-
+pkg::register_type!(Foo, Foo2);
@@ -0,0 +1,54 @@
+use pkg::sync::{IBase, IObjectBase, IObjectManager, ObjectManager};
+use pkg::sync::Data;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI32};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+#[derive(Debug, Default)]
+pub struct SyncBase {
+    pub id: AtomicI32,
+    pub name: RwLock<String>,
+    pub data: Mutex<Vec<u8>>,
+}
+
+impl IBase for SyncBase {
+    fn write(&self, data: &mut Data, o: &ObjectManager) {
+        o.write(data, &self.id);
+        o.write(data, &self.name);
+        o.write(data, &self.data);
+    }
+
+    fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        o.read(data, &self.id)?;
+        o.read(data, &self.name)?;
+        o.read(data, &self.data)?;
+        Ok(())
+    }
+
+    fn get_typeid(&self) -> u16 {
+        Self::get_static_typeid()
+    }
+}
+
+impl IObjectBase for SyncBase {
+    fn get_static_typeid() -> u16 {
+        101
+    }
+
+    fn new() -> Option<Arc<dyn IBase>> {
+        Some(Arc::new(Self::default()))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncFly {
+    pub alive: AtomicBool,
+    pub x: Mutex<f32>,
+    pub y: RwLock<f32>,
+    pub rc: RwLock<Option<Arc<SyncBase>>>,
+    pub wk: RwLock<Option<Weak<SyncBase>>>,
+    pub vec: Mutex<Vec<Arc<SyncBase>>>,
+    pub hash: Mutex<HashMap<i32, Arc<SyncBase>>>,
+}
+
+pkg::impl_sync_object!(SyncFly = 102 { alive, x, y, rc, wk, vec, hash });
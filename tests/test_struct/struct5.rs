@@ -0,0 +1,41 @@
+use crate::test_struct::Base;
+use pkg::interface::IBaseMut;
+use pkg::object_manager::IObjectManager;
+use pkg::{Data, ObjectManager};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PlainChild {
+    pub id: i32,
+    pub tags: Vec<String>,
+}
+
+impl IBaseMut for PlainChild {
+    fn write_mut(&self, data: &mut Data, o: &ObjectManager) {
+        o.write(data, &self.id);
+        o.write(data, &self.tags);
+    }
+
+    fn read_mut(&mut self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        o.read_mut(data, &mut self.id)?;
+        o.read_mut(data, &mut self.tags)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PlainPath {
+    pub x: i32,
+    pub y: f32,
+    pub name: Option<String>,
+    pub child: Option<Box<PlainChild>>,
+    pub list: Vec<PlainChild>,
+    pub dict: HashMap<i32, PlainChild>,
+    pub shared: Option<Rc<Base>>,
+}
+
+pkg::impl_base_mut!(PlainChild);
+
+// 按字段顺序生成 IBaseMut
+pkg::impl_base_mut!(PlainPath { x, y, name, child, list, dict, shared });
@@ -0,0 +1,60 @@
+use crate::test_struct::Base;
+use pkg::delta::{DirtyMask, ITrackChanges};
+use pkg::interface::{GetValue, IBase};
+use pkg::{Data, ObjectManager};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// 字段私有 只能通过标记脏位的设置方法修改
+/// 脏位序号为字段在 impl_track_changes! 中的位置
+#[derive(Debug, Default)]
+pub struct Player {
+    hp: Cell<i32>,
+    name: RefCell<String>,
+    target: RefCell<Option<Rc<Base>>>,
+    dirty: DirtyMask,
+}
+
+impl Player {
+    pub fn hp(&self) -> i32 {
+        self.hp.get()
+    }
+
+    pub fn set_hp(&self, v: i32) {
+        self.dirty.set_cell(0, &self.hp, v)
+    }
+
+    pub fn name(&self) -> String {
+        self.name.borrow().clone()
+    }
+
+    pub fn set_name(&self, v: &str) {
+        self.dirty.replace(1, &self.name, v.to_string());
+    }
+
+    pub fn target(&self) -> Option<Rc<Base>> {
+        self.target.get()
+    }
+
+    pub fn set_target(&self, v: Rc<Base>) {
+        self.dirty.set(2, &self.target, v)
+    }
+}
+
+impl IBase for Player {
+    fn write(&self, data: &mut Data, o: &ObjectManager) {
+        self.write_fields(u64::MAX, data, o)
+    }
+
+    fn read(&self, data: &mut Data, o: &ObjectManager) -> Result<(), u32> {
+        self.read_fields(u64::MAX, data, o)
+    }
+
+    fn get_typeid(&self) -> u16 {
+        4001
+    }
+}
+
+pkg::impl_track_changes!(Player(dirty) { hp, name, target });
+
+pkg::impl_delta_fields!(Player { hp, name, target });